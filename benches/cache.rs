@@ -0,0 +1,155 @@
+// Baseline benchmarks for the in-memory cache
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use pokemon_api_proxy::{
+    compressed_cache::CompressedCache, CacheConfig, CacheTrait, InmemoryCache,
+};
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::sync::Arc;
+use std::thread;
+
+const POKEMON_JSON: &str = r#"{"id": 25, "name": "pikachu", "height": 4, "weight": 60}"#;
+
+fn cache_with(max_size: u32, expiration: u32) -> InmemoryCache<String> {
+    InmemoryCache::new(CacheConfig {
+        r#type: "memory".to_string(),
+        max_size,
+        expiration,
+        ..Default::default()
+    })
+}
+
+fn filled_cache(entries: u32, max_size: u32, expiration: u32) -> InmemoryCache<String> {
+    let cache = cache_with(max_size, expiration);
+    for id in 0..entries {
+        let _ = cache.insert(format!("/pokemon/{}", id), POKEMON_JSON.to_string());
+    }
+    cache
+}
+
+fn bench_insert_get(c: &mut Criterion) {
+    let cache = filled_cache(1000, 2000, 3600);
+
+    c.bench_function("get_hit", |b| {
+        b.iter(|| cache.get(black_box("/pokemon/500")))
+    });
+    c.bench_function("get_miss", |b| {
+        b.iter(|| cache.get(black_box("/pokemon/5000")))
+    });
+    c.bench_function("insert_existing", |b| {
+        b.iter(|| cache.insert(black_box("/pokemon/500".to_string()), POKEMON_JSON.to_string()))
+    });
+}
+
+// A full Pokémon document is large (pikachu is ~250 KB, mostly `moves`)
+fn large_pokemon_json() -> String {
+    let moves: Vec<String> = (0..2000)
+        .map(|i| format!(r#"{{"move": {{"name": "move-{}", "url": "https://pokeapi.co/api/v2/move/{}/"}}}}"#, i, i))
+        .collect();
+    format!(r#"{{"id": 25, "name": "pikachu", "moves": [{}]}}"#, moves.join(","))
+}
+
+fn bench_large_value_hit(c: &mut Criterion) {
+    let value = large_pokemon_json();
+    let cache = cache_with(10, 3600);
+    let _ = cache.insert("/pokemon/25".to_string(), value.clone());
+
+    // Hits hand out an Arc; the deep clone is what each hit used to cost
+    c.bench_function("get_hit_large_value", |b| {
+        b.iter(|| cache.get(black_box("/pokemon/25")))
+    });
+    c.bench_function("clone_large_value", |b| b.iter(|| black_box(&value).clone()));
+}
+
+fn bench_compressed_entries(c: &mut Criterion) {
+    let value = large_pokemon_json();
+    let plain = cache_with(10, 3600);
+    let compressed: CompressedCache<String> = CompressedCache::new(CacheConfig::default());
+    let _ = plain.insert("/pokemon/25".to_string(), value.clone());
+    let _ = compressed.insert("/pokemon/25".to_string(), value.clone());
+
+    // Criterion only times; report the footprint alongside
+    println!(
+        "entry footprint: {} bytes uncompressed, {} bytes compressed",
+        value.len(),
+        compressed.stored_bytes()
+    );
+
+    c.bench_function("get_hit_large_uncompressed", |b| {
+        b.iter(|| plain.get(black_box("/pokemon/25")))
+    });
+    c.bench_function("get_hit_large_compressed", |b| {
+        b.iter(|| compressed.get(black_box("/pokemon/25")))
+    });
+    c.bench_function("insert_large_compressed", |b| {
+        b.iter(|| compressed.insert("/pokemon/25".to_string(), black_box(&value).clone()))
+    });
+}
+
+fn bench_concurrent_get(c: &mut Criterion) {
+    let cache: Arc<dyn CacheTrait<String>> = Arc::new(filled_cache(1000, 2000, 3600));
+
+    c.bench_function("concurrent_get_4_threads", |b| {
+        b.iter(|| {
+            let handles: Vec<_> = (0..4)
+                .map(|t| {
+                    let cache = cache.clone();
+                    thread::spawn(move || {
+                        for id in 0..250 {
+                            black_box(cache.get(&format!("/pokemon/{}", t * 250 + id)));
+                        }
+                    })
+                })
+                .collect();
+            for handle in handles {
+                handle.join().unwrap();
+            }
+        })
+    });
+}
+
+fn bench_eviction_at_capacity(c: &mut Criterion) {
+    let cache = filled_cache(1000, 1000, 3600);
+    let mut next_id = 1000u32;
+
+    // Every insert of a new key into the full cache scans for a victim
+    c.bench_function("insert_with_eviction_1000", |b| {
+        b.iter(|| {
+            next_id += 1;
+            cache.insert(format!("/pokemon/{}", next_id), POKEMON_JSON.to_string())
+        })
+    });
+}
+
+fn bench_cleanup_expired(c: &mut Criterion) {
+    // A zero TTL makes every entry expired as soon as it is inserted
+    c.bench_function("cleanup_10000_expired", |b| {
+        b.iter_batched(
+            || {
+                let cache = InmemoryCache::new(CacheConfig {
+                    max_size: 10_000,
+                    ttl: HashMap::from([("pokemon".to_string(), 0)]),
+                    ..Default::default()
+                });
+                for id in 0..10_000 {
+                    let _ = cache.insert(format!("/pokemon/{}", id), POKEMON_JSON.to_string());
+                }
+                cache
+            },
+            |cache| cache.cleanup_expired(),
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_insert_get,
+    bench_large_value_hit,
+    bench_compressed_entries,
+    bench_concurrent_get,
+    bench_eviction_at_capacity,
+    bench_cleanup_expired
+);
+criterion_main!(benches);
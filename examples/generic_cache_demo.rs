@@ -30,6 +30,7 @@ fn string_cache_example() {
         r#type: "memory".to_string(),
         max_size: 500,
         expiration: 1800, // 30 minutes
+        ..Default::default()
     };
     
     let cache: InmemoryCache<String> = InmemoryCache::new(config);
@@ -64,19 +65,20 @@ fn numeric_cache_example() {
         r#type: "memory".to_string(),
         max_size: 100,
         expiration: 300, // 5 minutes
+        ..Default::default()
     };
     
     let cache: InmemoryCache<f64> = InmemoryCache::new(config);
     
     // Cache computed mathematical results
-    let _ = cache.insert("pi".to_string(), 3.14159);
-    let _ = cache.insert("e".to_string(), 2.71828);
+    let _ = cache.insert("pi".to_string(), std::f64::consts::PI);
+    let _ = cache.insert("e".to_string(), std::f64::consts::E);
     let _ = cache.insert("golden_ratio".to_string(), 1.618033);
     
     // Retrieve and use cached calculations
     if let Some(pi) = cache.get("pi") {
         println!("✅ π = {}", pi);
-        println!("✅ Area of circle (r=5): {}", pi * 5.0 * 5.0);
+        println!("✅ Area of circle (r=5): {}", *pi * 5.0 * 5.0);
     }
     
     if let Some(e) = cache.get("e") {
@@ -89,7 +91,7 @@ fn numeric_cache_example() {
 fn custom_struct_example() {
     println!("\n🏗️ Custom Struct Cache Example:");
     
-    #[derive(Clone, Debug)]
+    #[derive(Clone, Debug, serde::Serialize)]
     struct UserSession {
         user_id: u64,
         token: String,
@@ -101,6 +103,7 @@ fn custom_struct_example() {
         r#type: "memory".to_string(),
         max_size: 10000,
         expiration: 7200, // 2 hours
+        ..Default::default()
     };
     
     let cache: InmemoryCache<UserSession> = InmemoryCache::new(config);
@@ -116,8 +119,8 @@ fn custom_struct_example() {
     
     // Retrieve session and check permissions
     if let Some(session) = cache.get("session:abc123xyz") {
-        println!("✅ User session: ID={}, Permissions={:?}", 
-                session.user_id, session.permissions);
+        println!("✅ User session: ID={}, Token={}, Expires={}, Permissions={:?}", 
+                session.user_id, session.token, session.expires_at, session.permissions);
     }
     
     println!("📊 Session cache size: {}", cache.size());
@@ -130,6 +133,7 @@ fn vector_cache_example() {
         r#type: "memory".to_string(),
         max_size: 50,
         expiration: 600, // 10 minutes
+        ..Default::default()
     };
     
     let cache: InmemoryCache<Vec<String>> = InmemoryCache::new(config);
@@ -169,6 +173,7 @@ async fn async_cache_example() {
         r#type: "memory".to_string(),
         max_size: 100,
         expiration: 3600,
+        ..Default::default()
     };
     
     let cache: InmemoryCache<String> = InmemoryCache::new(config);
@@ -215,6 +220,7 @@ fn demonstrate_trait_objects() {
         r#type: "memory".to_string(),
         max_size: 10,
         expiration: 300,
+        ..Default::default()
     };
     
     // Create different cache types as trait objects
@@ -0,0 +1,201 @@
+use crate::config::AccessLogFormat;
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, Request, State},
+    http::header,
+    middleware::Next,
+    response::Response,
+};
+use std::fmt;
+use std::net::SocketAddr;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::fmt::{format::Writer, FmtContext, FormatEvent, FormatFields};
+use tracing_subscriber::registry::LookupSpan;
+
+// Target of every access log event, so filters can enable or silence them
+pub const ACCESS_LOG_TARGET: &str = "access_log";
+// Target of Common Log Format lines, which `ClfFormat` prints bare
+pub const CLF_TARGET: &str = "access_log::clf";
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+// Middleware logging one line per request in the configured format
+pub async fn access_log(
+    State(format): State<AccessLogFormat>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let started = Instant::now();
+    let remote_ip = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip().to_string())
+        .unwrap_or_else(|| "-".to_string());
+    let method = request.method().clone();
+    let uri = request.uri().clone();
+    let version = request.version();
+
+    let response = next.run(request).await;
+
+    let status = response.status().as_u16();
+    let bytes = response
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+
+    match format {
+        AccessLogFormat::Structured => {
+            tracing::info!(
+                target: ACCESS_LOG_TARGET,
+                remote_ip = %remote_ip,
+                method = %method,
+                path = %uri,
+                status,
+                bytes = bytes.as_deref().unwrap_or("-"),
+                latency_ms = started.elapsed().as_millis() as u64,
+                "request completed"
+            );
+        }
+        AccessLogFormat::Clf => {
+            let request_line = format!("{} {} {:?}", method, uri, version);
+            tracing::info!(
+                target: CLF_TARGET,
+                "{}",
+                clf_line(&remote_ip, SystemTime::now(), &request_line, status, bytes.as_deref())
+            );
+        }
+    }
+
+    response
+}
+
+// Event format printing only the message, so CLF lines come out exactly as
+// log processors expect them, without the JSON envelope
+pub struct ClfFormat;
+
+impl<S, N> FormatEvent<S, N> for ClfFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(&self, ctx: &FmtContext<'_, S, N>, mut writer: Writer<'_>, event: &Event<'_>) -> fmt::Result {
+        ctx.field_format().format_fields(writer.by_ref(), event)?;
+        writeln!(writer)
+    }
+}
+
+// Format a Common Log Format line: host ident authuser [date] "request" status bytes
+pub fn clf_line(
+    remote_ip: &str,
+    time: SystemTime,
+    request_line: &str,
+    status: u16,
+    bytes: Option<&str>,
+) -> String {
+    format!(
+        "{} - - [{}] \"{}\" {} {}",
+        remote_ip,
+        clf_timestamp(time),
+        request_line,
+        status,
+        bytes.unwrap_or("-")
+    )
+}
+
+// Render a timestamp as `10/Oct/2000:13:55:36 +0000` (always UTC)
+fn clf_timestamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (days, secs_of_day) = (secs / 86_400, secs % 86_400);
+
+    // Convert days since the epoch to a civil date (Howard Hinnant's algorithm)
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:02}/{}/{}:{:02}:{:02}:{:02} +0000",
+        day,
+        MONTHS[(month - 1) as usize],
+        year,
+        secs_of_day / 3_600,
+        (secs_of_day % 3_600) / 60,
+        secs_of_day % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_clf_line_shape() {
+        // 2000-10-10 13:55:36 UTC
+        let time = UNIX_EPOCH + Duration::from_secs(971_186_136);
+        let line = clf_line(
+            "127.0.0.1",
+            time,
+            "GET /pokemon/25 HTTP/1.1",
+            200,
+            Some("2326"),
+        );
+
+        assert_eq!(
+            line,
+            r#"127.0.0.1 - - [10/Oct/2000:13:55:36 +0000] "GET /pokemon/25 HTTP/1.1" 200 2326"#
+        );
+    }
+
+    // Collects everything a subscriber writes
+    #[derive(Clone, Default)]
+    struct Captured(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_clf_events_print_bare_lines() {
+        let captured = Captured::default();
+        let subscriber = tracing_subscriber::fmt()
+            .event_format(ClfFormat)
+            .with_writer({
+                let captured = captured.clone();
+                move || captured.clone()
+            })
+            .finish();
+        tracing::subscriber::with_default(subscriber, || {
+            let _span = tracing::info_span!("request", id = "abc").entered();
+            tracing::info!(target: CLF_TARGET, "{}", r#"- - - [01/Jan/1970:00:00:00 +0000] "GET / HTTP/1.1" 200 2"#);
+        });
+
+        let output = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+        assert_eq!(output, "- - - [01/Jan/1970:00:00:00 +0000] \"GET / HTTP/1.1\" 200 2\n");
+    }
+
+    #[test]
+    fn test_clf_line_without_bytes() {
+        let line = clf_line("-", UNIX_EPOCH, "GET /random HTTP/1.1", 500, None);
+        assert_eq!(line, r#"- - - [01/Jan/1970:00:00:00 +0000] "GET /random HTTP/1.1" 500 -"#);
+    }
+}
@@ -0,0 +1,174 @@
+use crate::config::AdminConfig;
+use crate::error::error_response;
+use axum::{
+    extract::FromRequestParts,
+    http::{header, request::Parts, StatusCode},
+    response::Response,
+};
+use std::sync::Arc;
+use subtle::ConstantTimeEq;
+
+// Environment variable that takes precedence over every other token source
+pub const ADMIN_TOKEN_ENV: &str = "POKEMON_PROXY_ADMIN_TOKEN";
+
+// The resolved admin secret, installed on the router as a request extension
+#[derive(Clone, Debug)]
+pub struct AdminToken(pub Arc<str>);
+
+// Resolve the admin token with precedence env > file > inline config.
+// Returns Ok(None) when admin endpoints are disabled.
+pub fn resolve_admin_token(
+    config: &AdminConfig,
+    env_token: Option<String>,
+) -> Result<Option<AdminToken>, String> {
+    if !config.enabled {
+        return Ok(None);
+    }
+
+    let token = match (env_token, &config.admin_token_file, &config.admin_token) {
+        (Some(token), _, _) => token,
+        (None, Some(path), _) => std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read admin_token_file {}: {}", path, e))?,
+        (None, None, Some(token)) => token.expose().to_string(),
+        (None, None, None) => String::new(),
+    };
+
+    // Secret mounts usually end with a newline
+    let token = token.trim();
+    if token.is_empty() {
+        return Err(format!(
+            "Admin endpoints are enabled but no admin token was provided (set {}, admin_token_file or admin_token)",
+            ADMIN_TOKEN_ENV
+        ));
+    }
+
+    Ok(Some(AdminToken(Arc::from(token))))
+}
+
+// Extractor guarding admin endpoints: requires `Authorization: Bearer <token>`
+pub struct RequireAdmin;
+
+impl<S> FromRequestParts<S> for RequireAdmin
+where
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Some(AdminToken(expected)) = parts.extensions.get::<AdminToken>() else {
+            return Err(error_response(StatusCode::NOT_FOUND, "Admin endpoints are disabled"));
+        };
+
+        let provided = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+
+        match provided {
+            Some(provided) if tokens_match(provided, expected) => Ok(RequireAdmin),
+            _ => {
+                tracing::warn!("Rejected admin request with missing or invalid token");
+                Err(error_response(StatusCode::UNAUTHORIZED, "Unauthorized"))
+            }
+        }
+    }
+}
+
+// Compare tokens in constant time so response timing doesn't reveal how much
+// of the token matched (only the length can leak)
+fn tokens_match(provided: &str, expected: &str) -> bool {
+    provided.as_bytes().ct_eq(expected.as_bytes()).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Secret;
+    use axum::{body::Body, http::Request, routing::get, Extension, Router};
+    use tower::ServiceExt;
+
+    fn admin_config(token: Option<&str>, token_file: Option<String>) -> AdminConfig {
+        AdminConfig {
+            enabled: true,
+            admin_token: token.map(Secret::new),
+            admin_token_file: token_file,
+        }
+    }
+
+    fn resolved(token: Result<Option<AdminToken>, String>) -> String {
+        token.unwrap().unwrap().0.to_string()
+    }
+
+    #[test]
+    fn test_inline_token() {
+        let token = resolve_admin_token(&admin_config(Some("inline"), None), None);
+        assert_eq!(resolved(token), "inline");
+    }
+
+    #[test]
+    fn test_file_token_overrides_inline() {
+        let path = std::env::temp_dir().join(format!("admin-token-{}", std::process::id()));
+        std::fs::write(&path, "from-file\n").unwrap();
+        let config = admin_config(Some("inline"), Some(path.to_string_lossy().into_owned()));
+
+        let token = resolve_admin_token(&config, None);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(resolved(token), "from-file");
+    }
+
+    #[test]
+    fn test_env_token_overrides_file_and_inline() {
+        let config = admin_config(Some("inline"), Some("/nonexistent/token".to_string()));
+        let token = resolve_admin_token(&config, Some("from-env".to_string()));
+        assert_eq!(resolved(token), "from-env");
+    }
+
+    #[test]
+    fn test_missing_token_is_an_error() {
+        assert!(resolve_admin_token(&admin_config(None, None), None).is_err());
+        assert!(resolve_admin_token(&admin_config(Some("  "), None), None).is_err());
+
+        let unreadable = admin_config(None, Some("/nonexistent/token".to_string()));
+        assert!(resolve_admin_token(&unreadable, None).is_err());
+    }
+
+    #[test]
+    fn test_disabled_admin_needs_no_token() {
+        let config = AdminConfig::default();
+        assert!(resolve_admin_token(&config, None).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_tokens_match() {
+        assert!(tokens_match("secret", "secret"));
+        assert!(!tokens_match("secreT", "secret"));
+        assert!(!tokens_match("secre", "secret"));
+        assert!(!tokens_match("secret-and-more", "secret"));
+        assert!(!tokens_match("", "secret"));
+    }
+
+    #[tokio::test]
+    async fn test_require_admin_extractor() {
+        let app = Router::new()
+            .route("/admin", get(|_: RequireAdmin| async { "ok" }))
+            .layer(Extension(AdminToken(Arc::from("secret"))));
+
+        let request = |auth: Option<&str>| {
+            let mut builder = Request::get("/admin");
+            if let Some(auth) = auth {
+                builder = builder.header(header::AUTHORIZATION, auth);
+            }
+            builder.body(Body::empty()).unwrap()
+        };
+
+        let ok = app.clone().oneshot(request(Some("Bearer secret"))).await.unwrap();
+        assert_eq!(ok.status(), StatusCode::OK);
+        let wrong = app.clone().oneshot(request(Some("Bearer nope"))).await.unwrap();
+        assert_eq!(wrong.status(), StatusCode::UNAUTHORIZED);
+        let missing = app.oneshot(request(None)).await.unwrap();
+        assert_eq!(missing.status(), StatusCode::UNAUTHORIZED);
+        let body = axum::body::to_bytes(missing.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], br#"{"error":"Unauthorized","code":401}"#);
+    }
+}
@@ -0,0 +1,119 @@
+use crate::cache::CacheStats;
+use serde::Serialize;
+
+// Below this many lookups the ratios are too noisy to act on
+const MIN_LOOKUPS: u64 = 100;
+const LOW_HIT_RATE: f64 = 0.5;
+const HIGH_EVICTION_RATE: f64 = 0.1;
+const UNDERFILLED_RATIO: f64 = 0.25;
+
+#[derive(Debug, Serialize)]
+pub struct CacheAdvice {
+    pub hit_rate: f64,
+    pub eviction_rate: f64,
+    pub fill_ratio: f64,
+    pub recommendations: Vec<String>,
+}
+
+// Turn cache statistics into human-readable tuning recommendations
+pub fn cache_advice(stats: &CacheStats, size: usize, max_size: usize) -> CacheAdvice {
+    let hit_rate = stats.hit_rate();
+    let eviction_rate = if stats.inserts == 0 {
+        0.0
+    } else {
+        stats.evictions as f64 / stats.inserts as f64
+    };
+    let fill_ratio = if max_size == 0 {
+        0.0
+    } else {
+        size as f64 / max_size as f64
+    };
+
+    let mut recommendations = Vec::new();
+
+    if stats.hits + stats.misses < MIN_LOOKUPS {
+        recommendations.push(format!(
+            "not enough traffic yet: recommendations need at least {} lookups",
+            MIN_LOOKUPS
+        ));
+    } else {
+        if hit_rate < LOW_HIT_RATE && eviction_rate > HIGH_EVICTION_RATE {
+            recommendations.push(
+                "hit rate low and evictions high: consider increasing max_size".to_string(),
+            );
+        } else if hit_rate < LOW_HIT_RATE {
+            recommendations.push(
+                "hit rate low with few evictions: entries may expire too soon, consider increasing expiration"
+                    .to_string(),
+            );
+        }
+
+        if fill_ratio < UNDERFILLED_RATIO && stats.evictions == 0 {
+            recommendations.push("cache underfilled: max_size could be reduced".to_string());
+        }
+
+        if recommendations.is_empty() {
+            recommendations.push("cache looks healthy: no changes recommended".to_string());
+        }
+    }
+
+    CacheAdvice {
+        hit_rate,
+        eviction_rate,
+        fill_ratio,
+        recommendations,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(hits: u64, misses: u64, inserts: u64, evictions: u64) -> CacheStats {
+        CacheStats {
+            hits,
+            misses,
+            inserts,
+            evictions,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_not_enough_traffic() {
+        let advice = cache_advice(&stats(5, 5, 5, 0), 5, 1000);
+        assert_eq!(advice.recommendations.len(), 1);
+        assert!(advice.recommendations[0].starts_with("not enough traffic"));
+    }
+
+    #[test]
+    fn test_low_hit_rate_high_evictions() {
+        let advice = cache_advice(&stats(20, 180, 180, 90), 100, 100);
+        assert!(advice.recommendations[0].contains("consider increasing max_size"));
+    }
+
+    #[test]
+    fn test_low_hit_rate_few_evictions() {
+        let advice = cache_advice(&stats(20, 180, 180, 0), 90, 100);
+        assert!(advice.recommendations[0].contains("consider increasing expiration"));
+    }
+
+    #[test]
+    fn test_underfilled() {
+        let advice = cache_advice(&stats(900, 100, 100, 0), 100, 1000);
+        assert_eq!(
+            advice.recommendations,
+            vec!["cache underfilled: max_size could be reduced"]
+        );
+        assert!((advice.fill_ratio - 0.1).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_healthy() {
+        let advice = cache_advice(&stats(900, 100, 100, 0), 90, 100);
+        assert_eq!(
+            advice.recommendations,
+            vec!["cache looks healthy: no changes recommended"]
+        );
+    }
+}
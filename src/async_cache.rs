@@ -0,0 +1,310 @@
+use crate::cache::{CacheError, CacheStats, CacheTrait, EntryAge};
+use futures::future::BoxFuture;
+use std::ops::Deref;
+use std::sync::Arc;
+use std::time::Duration;
+
+// The cache operations on the request path, as futures, for backends that do
+// IO (Redis, disk) and shouldn't hold a runtime thread while they wait. The
+// futures are boxed so the trait stays usable as `dyn AsyncCacheTrait<T>`.
+// Counters kept in memory (stats, hit rate, access counts) stay on `CacheTrait`.
+pub trait AsyncCacheTrait<T>: CacheTrait<T>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<Arc<T>>>;
+    fn insert(&self, key: String, value: T) -> BoxFuture<'_, Result<(), CacheError>>;
+    fn remove<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<Arc<T>>>;
+    fn clear(&self) -> BoxFuture<'_, ()>;
+    fn size(&self) -> BoxFuture<'_, usize>;
+    fn keys(&self) -> BoxFuture<'_, Vec<String>>;
+    fn peek<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<Arc<T>>>;
+    fn entry_age<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<EntryAge>>;
+}
+
+// Run blocking IO on tokio's blocking pool, so the runtime thread that awaits
+// it stays free for other requests
+pub(crate) async fn unblock<R>(io: impl FnOnce() -> R + Send + 'static) -> Result<R, CacheError>
+where
+    R: Send + 'static,
+{
+    tokio::task::spawn_blocking(io)
+        .await
+        .map_err(|e| CacheError::Backend(e.to_string()))
+}
+
+// Adapter for caches that never wait on IO, such as `InmemoryCache`: each
+// operation runs when it is called and the returned future is already
+// complete. Don't wrap a backend that blocks; implement the trait for it.
+pub struct SyncCache<C>(C);
+
+impl<C> SyncCache<C> {
+    pub fn new(cache: C) -> Self {
+        Self(cache)
+    }
+}
+
+// The wrapped cache's own methods (`subscribe`, `byte_size`, ...) stay reachable
+impl<C> Deref for SyncCache<C> {
+    type Target = C;
+
+    fn deref(&self) -> &C {
+        &self.0
+    }
+}
+
+impl<T, C> CacheTrait<T> for SyncCache<C>
+where
+    T: Clone + Send + Sync,
+    C: CacheTrait<T>,
+{
+    fn get(&self, key: &str) -> Option<Arc<T>> {
+        self.0.get(key)
+    }
+
+    fn insert(&self, key: String, value: T) -> Result<(), CacheError> {
+        self.0.insert(key, value)
+    }
+
+    fn remove(&self, key: &str) -> Option<Arc<T>> {
+        self.0.remove(key)
+    }
+
+    fn clear(&self) {
+        self.0.clear()
+    }
+
+    fn size(&self) -> usize {
+        self.0.size()
+    }
+
+    fn hit_rate(&self) -> f64 {
+        self.0.hit_rate()
+    }
+
+    fn cleanup_expired(&self) {
+        self.0.cleanup_expired()
+    }
+
+    fn insert_with_ttl(&self, key: String, value: T, ttl: Duration) -> Result<(), CacheError> {
+        self.0.insert_with_ttl(key, value, ttl)
+    }
+
+    fn stats(&self) -> Option<CacheStats> {
+        self.0.stats()
+    }
+
+    fn keys(&self) -> Vec<String> {
+        self.0.keys()
+    }
+
+    fn peek(&self, key: &str) -> Option<Arc<T>> {
+        self.0.peek(key)
+    }
+
+    fn entry_age(&self, key: &str) -> Option<EntryAge> {
+        self.0.entry_age(key)
+    }
+
+    fn access_counts(&self) -> Vec<(String, u64)> {
+        self.0.access_counts()
+    }
+}
+
+impl<T, C> AsyncCacheTrait<T> for SyncCache<C>
+where
+    T: Clone + Send + Sync + 'static,
+    C: CacheTrait<T>,
+{
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<Arc<T>>> {
+        Box::pin(std::future::ready(self.0.get(key)))
+    }
+
+    fn insert(&self, key: String, value: T) -> BoxFuture<'_, Result<(), CacheError>> {
+        Box::pin(std::future::ready(self.0.insert(key, value)))
+    }
+
+    fn remove<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<Arc<T>>> {
+        Box::pin(std::future::ready(self.0.remove(key)))
+    }
+
+    fn clear(&self) -> BoxFuture<'_, ()> {
+        self.0.clear();
+        Box::pin(std::future::ready(()))
+    }
+
+    fn size(&self) -> BoxFuture<'_, usize> {
+        Box::pin(std::future::ready(self.0.size()))
+    }
+
+    fn keys(&self) -> BoxFuture<'_, Vec<String>> {
+        Box::pin(std::future::ready(self.0.keys()))
+    }
+
+    fn peek<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<Arc<T>>> {
+        Box::pin(std::future::ready(self.0.peek(key)))
+    }
+
+    fn entry_age<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<EntryAge>> {
+        Box::pin(std::future::ready(self.0.entry_age(key)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CacheConfig, InmemoryCache};
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    // Stands in for a network backend: every async operation yields to the
+    // runtime before touching the map, as a real round trip would
+    #[derive(Default)]
+    struct RemoteCache {
+        entries: Mutex<HashMap<String, Arc<String>>>,
+    }
+
+    impl CacheTrait<String> for RemoteCache {
+        fn get(&self, key: &str) -> Option<Arc<String>> {
+            self.entries.lock().unwrap().get(key).cloned()
+        }
+
+        fn insert(&self, key: String, value: String) -> Result<(), CacheError> {
+            self.entries.lock().unwrap().insert(key, Arc::new(value));
+            Ok(())
+        }
+
+        fn remove(&self, key: &str) -> Option<Arc<String>> {
+            self.entries.lock().unwrap().remove(key)
+        }
+
+        fn clear(&self) {
+            self.entries.lock().unwrap().clear();
+        }
+
+        fn size(&self) -> usize {
+            self.entries.lock().unwrap().len()
+        }
+
+        fn hit_rate(&self) -> f64 {
+            0.0
+        }
+
+        fn cleanup_expired(&self) {}
+
+        fn keys(&self) -> Vec<String> {
+            self.entries.lock().unwrap().keys().cloned().collect()
+        }
+
+        fn peek(&self, key: &str) -> Option<Arc<String>> {
+            CacheTrait::get(self, key)
+        }
+    }
+
+    impl AsyncCacheTrait<String> for RemoteCache {
+        fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<Arc<String>>> {
+            Box::pin(async move {
+                tokio::task::yield_now().await;
+                CacheTrait::get(self, key)
+            })
+        }
+
+        fn insert(&self, key: String, value: String) -> BoxFuture<'_, Result<(), CacheError>> {
+            Box::pin(async move {
+                tokio::task::yield_now().await;
+                CacheTrait::insert(self, key, value)
+            })
+        }
+
+        fn remove<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<Arc<String>>> {
+            Box::pin(async move {
+                tokio::task::yield_now().await;
+                CacheTrait::remove(self, key)
+            })
+        }
+
+        fn clear(&self) -> BoxFuture<'_, ()> {
+            Box::pin(async move {
+                tokio::task::yield_now().await;
+                CacheTrait::clear(self);
+            })
+        }
+
+        fn size(&self) -> BoxFuture<'_, usize> {
+            Box::pin(async move {
+                tokio::task::yield_now().await;
+                CacheTrait::size(self)
+            })
+        }
+
+        fn keys(&self) -> BoxFuture<'_, Vec<String>> {
+            Box::pin(async move {
+                tokio::task::yield_now().await;
+                CacheTrait::keys(self)
+            })
+        }
+
+        fn peek<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<Arc<String>>> {
+            Box::pin(async move {
+                tokio::task::yield_now().await;
+                CacheTrait::peek(self, key)
+            })
+        }
+
+        fn entry_age<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<EntryAge>> {
+            Box::pin(async move {
+                tokio::task::yield_now().await;
+                CacheTrait::entry_age(self, key)
+            })
+        }
+    }
+
+    async fn exercise(cache: &dyn AsyncCacheTrait<String>) {
+        assert_eq!(AsyncCacheTrait::get(cache, "/pokemon/25").await, None);
+
+        AsyncCacheTrait::insert(cache, "/pokemon/25".to_string(), "pikachu".to_string()).await.unwrap();
+        AsyncCacheTrait::insert(cache, "/pokemon/1".to_string(), "bulbasaur".to_string()).await.unwrap();
+        assert_eq!(
+            AsyncCacheTrait::get(cache, "/pokemon/25").await.as_deref().map(String::as_str),
+            Some("pikachu")
+        );
+        // The sync side sees the same entries
+        assert_eq!(CacheTrait::size(cache), 2);
+        assert_eq!(AsyncCacheTrait::size(cache).await, 2);
+        let mut keys = AsyncCacheTrait::keys(cache).await;
+        keys.sort();
+        assert_eq!(keys, ["/pokemon/1", "/pokemon/25"]);
+        assert_eq!(
+            AsyncCacheTrait::peek(cache, "/pokemon/1").await.as_deref().map(String::as_str),
+            Some("bulbasaur")
+        );
+
+        assert_eq!(
+            AsyncCacheTrait::remove(cache, "/pokemon/25").await.as_deref().map(String::as_str),
+            Some("pikachu")
+        );
+        assert_eq!(AsyncCacheTrait::get(cache, "/pokemon/25").await, None);
+
+        AsyncCacheTrait::clear(cache).await;
+        assert_eq!(AsyncCacheTrait::get(cache, "/pokemon/1").await, None);
+    }
+
+    #[tokio::test]
+    async fn test_sync_cache_adapter() {
+        let cache = SyncCache::new(InmemoryCache::<String>::new(CacheConfig::default()));
+        exercise(&cache).await;
+        // The wrapped cache's own methods pass through
+        assert_eq!(cache.byte_size(), 0);
+
+        // Usable as the trait object the app holds, and as the sync trait
+        let cache: Arc<dyn AsyncCacheTrait<String>> = Arc::new(SyncCache::new(InmemoryCache::new(CacheConfig::default())));
+        exercise(cache.as_ref()).await;
+        let _: Arc<dyn CacheTrait<String>> = cache;
+    }
+
+    #[tokio::test]
+    async fn test_async_trait_over_async_backend() {
+        let cache: Arc<dyn AsyncCacheTrait<String>> = Arc::new(RemoteCache::default());
+        exercise(cache.as_ref()).await;
+    }
+}
@@ -1,7 +1,13 @@
 use crate::config::CacheConfig;
+use serde::Serialize;
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::sync::atomic::{self, AtomicUsize};
 use std::sync::{Arc, Mutex};
-use std::time::{Duration, Instant};
+use std::time::Duration;
+use tokio::sync::broadcast;
+// tokio's clock, so tests can pause and advance it instead of sleeping
+use tokio::time::Instant;
 
 // Custom error types for cache operations
 #[derive(Debug)]
@@ -9,6 +15,8 @@ pub enum CacheError {
     LockError(String),
     MaxSizeExceeded,
     InvalidKey(String),
+    Serialization(String),
+    Backend(String),
 }
 
 impl std::fmt::Display for CacheError {
@@ -17,60 +25,124 @@ impl std::fmt::Display for CacheError {
             CacheError::LockError(msg) => write!(f, "Cache lock error: {}", msg),
             CacheError::MaxSizeExceeded => write!(f, "Cache maximum size exceeded"),
             CacheError::InvalidKey(key) => write!(f, "Invalid cache key: {}", key),
+            CacheError::Serialization(msg) => write!(f, "Cache serialization error: {}", msg),
+            CacheError::Backend(msg) => write!(f, "Cache backend error: {}", msg),
         }
     }
 }
 
 impl std::error::Error for CacheError {}
 
+// Upper bound on how many slots are pre-allocated up front, so an absurd
+// max_size doesn't reserve gigabytes before anything is cached
+const MAX_PRESIZE_CAPACITY: usize = 65_536;
+
 // Cache entry with expiration support
 #[derive(Debug, Clone)]
 struct CacheEntry<T> {
-    value: T,
+    value: Arc<T>,
     created_at: Instant,
+    last_accessed: Instant,
+    ttl: Duration,
     access_count: u64,
+    // Footprint as measured by the cache's sizer, 0 when it has none
+    bytes: usize,
 }
 
 impl<T: Clone> CacheEntry<T> {
-    fn new(value: T) -> Self {
+    fn new(value: T, ttl: Duration, bytes: usize) -> Self {
+        let now = Instant::now();
         Self {
-            value,
-            created_at: Instant::now(),
+            value: Arc::new(value),
+            created_at: now,
+            last_accessed: now,
+            ttl,
             access_count: 1,
+            bytes,
         }
     }
 
-    fn is_expired(&self, expiration_duration: Duration) -> bool {
-        self.created_at.elapsed() > expiration_duration
+    fn is_expired(&self) -> bool {
+        self.created_at.elapsed() > self.ttl
     }
 
-    fn access(&mut self) -> T {
+    // Hand out a shared handle so hits never deep-copy the value
+    fn access(&mut self) -> Arc<T> {
         self.access_count += 1;
-        self.value.clone()
+        self.last_accessed = Instant::now();
+        Arc::clone(&self.value)
     }
 }
 
-// Cache trait for different implementations
+// Freshness of a live cache entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EntryAge {
+    pub age: Duration,
+    pub ttl_remaining: Duration,
+}
+
+// Cache trait for different implementations. Methods added after the first
+// release have defaults, so existing implementations keep compiling; a
+// backend overrides the ones it can support.
 pub trait CacheTrait<T>: Send + Sync
 where
     T: Clone + Send + Sync,
 {
-    fn get(&self, key: &str) -> Option<T>;
+    fn get(&self, key: &str) -> Option<Arc<T>>;
     fn insert(&self, key: String, value: T) -> Result<(), CacheError>;
-    fn remove(&self, key: &str) -> Option<T>;
+    fn remove(&self, key: &str) -> Option<Arc<T>>;
     fn clear(&self);
     fn size(&self) -> usize;
     fn hit_rate(&self) -> f64;
     fn cleanup_expired(&self);
+
+    // Insert with an explicit lifetime instead of the configured one for the
+    // key. Backends without per-entry lifetimes fall back to `insert`.
+    fn insert_with_ttl(&self, key: String, value: T, _ttl: Duration) -> Result<(), CacheError> {
+        self.insert(key, value)
+    }
+
+    fn stats(&self) -> Option<CacheStats> {
+        None
+    }
+
+    fn keys(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    // Read a live value without counting a hit or touching its access count
+    fn peek(&self, _key: &str) -> Option<Arc<T>> {
+        None
+    }
+
+    fn entry_age(&self, _key: &str) -> Option<EntryAge> {
+        None
+    }
+
+    // Live keys with their access counts, most accessed first
+    fn access_counts(&self) -> Vec<(String, u64)> {
+        Vec::new()
+    }
+}
+
+// A single cache operation, published to subscribers of the event channel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheEvent {
+    Hit(String),
+    Miss(String),
+    Insert(String),
+    Evict(String),
+    Expire(String),
 }
 
 // Statistics for cache monitoring
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize)]
 pub struct CacheStats {
     pub hits: u64,
     pub misses: u64,
     pub inserts: u64,
     pub removes: u64,
+    pub evictions: u64,
     pub cleanups: u64,
 }
 
@@ -84,42 +156,90 @@ impl CacheStats {
     }
 }
 
+// Measures a value's footprint for the `max_bytes` budget
+pub type Sizer<T> = fn(&T) -> Result<usize, CacheError>;
+
+// Sizer counting a value as the length of its JSON serialization
+pub fn json_len<T: Serialize>(value: &T) -> Result<usize, CacheError> {
+    serde_json::to_vec(value)
+        .map(|json| json.len())
+        .map_err(|e| CacheError::Serialization(e.to_string()))
+}
+
+// Told the key of each entry the cache drops on its own
+type DiscardListener = Box<dyn Fn(&str) + Send + Sync>;
+
 // In-memory cache implementation
 pub struct InmemoryCache<T>
 where
     T: Clone + Send + Sync,
 {
+    // Lock order: `stats` is never locked while `store` is held. Stats are
+    // updated through `record`, after the store guard is dropped.
     store: Arc<Mutex<HashMap<String, CacheEntry<T>>>>,
+    // Sum of the entries' `bytes`, only changed while `store` is held
+    bytes: AtomicUsize,
+    sizer: Option<Sizer<T>>,
+    on_discard: Option<DiscardListener>,
     config: CacheConfig,
     stats: Arc<Mutex<CacheStats>>,
+    events: Option<broadcast::Sender<CacheEvent>>,
 }
 
 impl<T> InmemoryCache<T>
 where
     T: Clone + Send + Sync,
 {
-    pub fn new(config: CacheConfig) -> Self {
+    pub fn new(mut config: CacheConfig) -> Self {
+        // A no-op when the app already clamped it at load time
+        config.clamp_max_size();
+
         tracing::info!(
             "Initializing in-memory cache with max_size: {}, expiration: {}s",
             config.max_size,
             config.expiration
         );
 
+        // Pre-size the map for its configured capacity to avoid rehashing during warmup
+        let capacity = (config.max_size as usize).min(MAX_PRESIZE_CAPACITY);
+
+        // A broadcast channel keeps only the newest `capacity` events, so a slow
+        // subscriber loses the oldest ones (and sees `Lagged`) instead of
+        // holding up the cache
+        let events = config
+            .event_channel_capacity
+            .map(|capacity| broadcast::channel(capacity.max(1)).0);
+
         Self {
-            store: Arc::new(Mutex::new(HashMap::new())),
+            store: Arc::new(Mutex::new(HashMap::with_capacity(capacity))),
+            bytes: AtomicUsize::new(0),
+            sizer: None,
+            on_discard: None,
             config,
             stats: Arc::new(Mutex::new(CacheStats::default())),
+            events,
         }
     }
 
+    // Enforce `max_bytes`, measuring each entry with `sizer` on insert.
+    // Without a sizer `max_bytes` is ignored, and values are only measured
+    // while it is set.
+    pub fn with_sizer(mut self, sizer: Sizer<T>) -> Self {
+        self.sizer = Some(sizer);
+        self
+    }
+
+    // Call `listener` with the key of every entry the cache drops on its own,
+    // evicted to make room or found expired; explicit removes aren't reported.
+    // It runs while the store is locked, so it must not block.
+    pub fn with_discard_listener(mut self, listener: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        self.on_discard = Some(Box::new(listener));
+        self
+    }
+
     // Create with default configuration
     pub fn with_defaults() -> Self {
-        let default_config = CacheConfig {
-            r#type: "memory".to_string(),
-            max_size: 1000,
-            expiration: 3600, // 1 hour
-        };
-        Self::new(default_config)
+        Self::new(CacheConfig::default())
     }
 
     // Check if cache is enabled based on config
@@ -132,75 +252,111 @@ where
         &self.config
     }
 
-    // Evict least recently used entries when cache is full
-    fn evict_lru(&self, store: &mut HashMap<String, CacheEntry<T>>) -> Result<(), CacheError> {
-        if store.len() < self.config.max_size as usize {
-            return Ok(());
+    // Receive every cache event from now on, or `None` when the event channel
+    // isn't configured
+    pub fn subscribe(&self) -> Option<broadcast::Receiver<CacheEvent>> {
+        self.events.as_ref().map(broadcast::Sender::subscribe)
+    }
+
+    // Publish an event; the key is only cloned when someone is listening
+    fn emit(&self, event: impl FnOnce() -> CacheEvent) {
+        if let Some(events) = &self.events
+            && events.receiver_count() > 0
+        {
+            let _ = events.send(event());
         }
+    }
 
-        // Find the entry with the oldest access time and lowest access count
-        let lru_key = store
-            .iter()
-            .min_by(|a, b| {
-                a.1.created_at
-                    .cmp(&b.1.created_at)
-                    .then_with(|| a.1.access_count.cmp(&b.1.access_count))
-            })
-            .map(|(key, _)| key.clone());
-
-        if let Some(key) = lru_key {
-            store.remove(&key);
-            tracing::debug!("Evicted LRU cache entry: {}", key);
-            
-            // Update stats
-            if let Ok(mut stats) = self.stats.lock() {
-                stats.removes += 1;
-            }
+    // Apply `update` to the stats. Takes no other lock, so callers must have
+    // released `store` first.
+    fn record(&self, update: impl FnOnce(&mut CacheStats)) {
+        if let Ok(mut stats) = self.stats.lock() {
+            update(&mut stats);
+        }
+    }
+
+    // The byte budget, when there is a sizer to enforce it with
+    fn max_bytes(&self) -> Option<usize> {
+        self.sizer.and(self.config.max_bytes)
+    }
+
+    // Whether an incoming entry of `incoming` bytes needs room made for it
+    fn over_budget(&self, store: &HashMap<String, CacheEntry<T>>, incoming: usize) -> bool {
+        store.len() >= self.config.max_size as usize
+            || self
+                .max_bytes()
+                .is_some_and(|max_bytes| self.bytes.load(atomic::Ordering::Relaxed) + incoming > max_bytes)
+    }
+
+    fn discard(&self, store: &mut HashMap<String, CacheEntry<T>>, key: &str) {
+        self.take(store, key);
+        if let Some(listener) = &self.on_discard {
+            listener(key);
         }
+    }
 
-        Ok(())
+    // Remove an entry and take its bytes off the running total. The caller
+    // holds `store`.
+    fn take(&self, store: &mut HashMap<String, CacheEntry<T>>, key: &str) -> Option<CacheEntry<T>> {
+        let entry = store.remove(key)?;
+        self.bytes.fetch_sub(entry.bytes, atomic::Ordering::Relaxed);
+        Some(entry)
     }
 
-    // Clean up expired entries
-    fn cleanup_expired_entries(&self) {
-        let expiration_duration = Duration::from_secs(self.config.expiration as u64);
-        
-        if let Ok(mut store) = self.store.lock() {
-            let expired_keys: Vec<String> = store
+    // Evict one entry, chosen by the eviction policy. False when the store is
+    // empty. The caller holds `store` and records the eviction once it's released.
+    fn evict(&self, store: &mut HashMap<String, CacheEntry<T>>) -> bool {
+        let victim = match self.config.eviction_policy.as_str() {
+            // Fewest reads, ties going to the one read longest ago
+            "lfu" => store
                 .iter()
-                .filter(|(_, entry)| entry.is_expired(expiration_duration))
-                .map(|(key, _)| key.clone())
-                .collect();
-
-            let expired_count = expired_keys.len();
-            for key in expired_keys {
-                store.remove(&key);
-                tracing::debug!("Removed expired cache entry: {}", key);
-            }
+                .min_by_key(|(_, entry)| (entry.access_count, entry.last_accessed)),
+            // The entry that has gone longest without being read
+            _ => store.iter().min_by_key(|(_, entry)| entry.last_accessed),
+        }
+        .map(|(key, _)| key.clone());
 
-            if expired_count > 0 {
-                tracing::debug!("Cleaned up {} expired cache entries", expired_count);
-                
-                // Update stats
-                if let Ok(mut stats) = self.stats.lock() {
-                    stats.cleanups += 1;
-                    stats.removes += expired_count as u64;
-                }
-            }
-        } else {
+        let Some(key) = victim else {
+            return false;
+        };
+        self.discard(store, &key);
+        tracing::debug!("Evicted {} cache entry: {}", self.config.eviction_policy, key);
+        self.emit(|| CacheEvent::Evict(key));
+        true
+    }
+
+    // Clean up expired entries
+    fn cleanup_expired_entries(&self) {
+        let Ok(mut store) = self.store.lock() else {
             tracing::error!("Failed to acquire lock for cache cleanup");
+            return;
+        };
+        let expired_keys: Vec<String> = store
+            .iter()
+            .filter(|(_, entry)| entry.is_expired())
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        let expired_count = expired_keys.len();
+        for key in expired_keys {
+            self.discard(&mut store, &key);
+            tracing::debug!("Removed expired cache entry: {}", key);
+            self.emit(|| CacheEvent::Expire(key));
+        }
+        drop(store);
+
+        if expired_count > 0 {
+            tracing::debug!("Cleaned up {} expired cache entries", expired_count);
+            self.record(|stats| {
+                stats.cleanups += 1;
+                stats.removes += expired_count as u64;
+            });
         }
     }
 
     // Get detailed cache statistics
     pub fn stats(&self) -> Option<CacheStats> {
-        self.stats.lock().ok().map(|stats| CacheStats {
-            hits: stats.hits,
-            misses: stats.misses,
-            inserts: stats.inserts,
-            removes: stats.removes,
-            cleanups: stats.cleanups,
-        })
+        self.stats.lock().ok().map(|stats| stats.clone())
     }
 
     // Check if a key exists without retrieving the value
@@ -212,6 +368,12 @@ where
         }
     }
 
+    // Approximate bytes held by all entries, as counted against `max_bytes`.
+    // Always 0 while the budget isn't enforced.
+    pub fn byte_size(&self) -> usize {
+        self.bytes.load(atomic::Ordering::Relaxed)
+    }
+
     // Get all cached Pokemon IDs
     pub fn keys(&self) -> Vec<String> {
         if let Ok(store) = self.store.lock() {
@@ -220,6 +382,68 @@ where
             Vec::new()
         }
     }
+
+    // Get all cached keys in a stable, numeric-aware order ("2" before "10")
+    pub fn keys_sorted(&self) -> Vec<String> {
+        let mut keys = self.keys();
+        keys.sort_by(|a, b| natural_cmp(a, b));
+        keys
+    }
+}
+
+// TTL for a key: the per-kind override for its resource kind, else the global expiration
+pub(crate) fn ttl_for_key(config: &CacheConfig, key: &str) -> Duration {
+    let secs = config
+        .ttl
+        .get(resource_kind(key))
+        .copied()
+        .unwrap_or(config.expiration);
+    Duration::from_secs(secs as u64)
+}
+
+// Resource kind of a cache key: its first segment, so "/pokemon/25" and
+// "pokemon:25" are both "pokemon" and "/pokemon-species/25" is "pokemon-species"
+fn resource_kind(key: &str) -> &str {
+    let key = key.trim_start_matches('/');
+    key.split(['/', ':']).next().unwrap_or(key)
+}
+
+// Compare strings treating runs of ASCII digits as numbers, so "/pokemon/2"
+// sorts before "/pokemon/10"
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a.as_bytes(), b.as_bytes());
+
+    while let (Some(&ca), Some(&cb)) = (a.first(), b.first()) {
+        if ca.is_ascii_digit() && cb.is_ascii_digit() {
+            let a_len = a.iter().take_while(|c| c.is_ascii_digit()).count();
+            let b_len = b.iter().take_while(|c| c.is_ascii_digit()).count();
+            let (a_num, b_num) = (&a[..a_len], &b[..b_len]);
+
+            // Strip leading zeros so digit-count comparison matches numeric value
+            let a_trimmed = &a_num[a_num.iter().take_while(|&&c| c == b'0').count()..];
+            let b_trimmed = &b_num[b_num.iter().take_while(|&&c| c == b'0').count()..];
+
+            let ordering = a_trimmed
+                .len()
+                .cmp(&b_trimmed.len())
+                .then_with(|| a_trimmed.cmp(b_trimmed))
+                .then_with(|| a_len.cmp(&b_len));
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+
+            a = &a[a_len..];
+            b = &b[b_len..];
+        } else {
+            if ca != cb {
+                return ca.cmp(&cb);
+            }
+            a = &a[1..];
+            b = &b[1..];
+        }
+    }
+
+    a.len().cmp(&b.len())
 }
 
 impl<T> Default for InmemoryCache<T>
@@ -235,132 +459,135 @@ impl<T> CacheTrait<T> for InmemoryCache<T>
 where
     T: Clone + Send + Sync,
 {
-    fn get(&self, key: &str) -> Option<T> {
+    fn get(&self, key: &str) -> Option<Arc<T>> {
         if key.is_empty() {
             tracing::warn!("Attempted to get cache entry with empty key");
             return None;
         }
 
-        let expiration_duration = Duration::from_secs(self.config.expiration as u64);
-
-        match self.store.lock() {
-            Ok(mut store) => {
-                if let Some(entry) = store.get_mut(key) {
-                    if entry.is_expired(expiration_duration) {
-                        tracing::debug!("Cache entry expired for key: {}", key);
-                        store.remove(key);
-                        
-                        // Update stats
-                        if let Ok(mut stats) = self.stats.lock() {
-                            stats.misses += 1;
-                        }
-                        
-                        None
-                    } else {
-                        tracing::debug!("Cache hit for key: {}", key);
-                        
-                        // Update stats
-                        if let Ok(mut stats) = self.stats.lock() {
-                            stats.hits += 1;
-                        }
-                        
-                        Some(entry.access())
-                    }
-                } else {
+        let found = match self.store.lock() {
+            Ok(mut store) => match store.get_mut(key) {
+                Some(entry) if entry.is_expired() => {
+                    tracing::debug!("Cache entry expired for key: {}", key);
+                    self.discard(&mut store, key);
+                    self.emit(|| CacheEvent::Expire(key.to_string()));
+                    self.emit(|| CacheEvent::Miss(key.to_string()));
+                    None
+                }
+                Some(entry) => {
+                    tracing::debug!("Cache hit for key: {}", key);
+                    self.emit(|| CacheEvent::Hit(key.to_string()));
+                    Some(entry.access())
+                }
+                None => {
                     tracing::debug!("Cache miss for key: {}", key);
-                    
-                    // Update stats
-                    if let Ok(mut stats) = self.stats.lock() {
-                        stats.misses += 1;
-                    }
-                    
+                    self.emit(|| CacheEvent::Miss(key.to_string()));
                     None
                 }
-            }
+            },
             Err(e) => {
                 tracing::error!("Failed to acquire cache read lock for key {}: {}", key, e);
-                None
+                return None;
             }
-        }
+        };
+
+        self.record(|stats| match found {
+            Some(_) => stats.hits += 1,
+            None => stats.misses += 1,
+        });
+        found
     }
 
     fn insert(&self, key: String, value: T) -> Result<(), CacheError> {
+        let ttl = ttl_for_key(&self.config, &key);
+        self.insert_with_ttl(key, value, ttl)
+    }
+
+    fn insert_with_ttl(&self, key: String, value: T, ttl: Duration) -> Result<(), CacheError> {
         if key.is_empty() {
             return Err(CacheError::InvalidKey("Key cannot be empty".to_string()));
         }
 
-        match self.store.lock() {
-            Ok(mut store) => {
-                // Check if we need to evict entries before inserting
-                if store.len() >= self.config.max_size as usize && !store.contains_key(&key) {
-                    self.evict_lru(&mut store)?;
-                }
-
-                let was_present = store.insert(key.clone(), CacheEntry::new(value)).is_some();
-                
-                if was_present {
-                    tracing::debug!("Updated existing Pokémon in cache: {}", key);
-                } else {
-                    tracing::debug!("Inserted new Pokémon into cache: {}", key);
-                }
-
-                // Update stats
-                if let Ok(mut stats) = self.stats.lock() {
-                    stats.inserts += 1;
-                }
+        // Only measured when there's a budget to count it against, and no
+        // amount of eviction makes room for an entry over the whole budget
+        let bytes = match (self.sizer, self.max_bytes()) {
+            (Some(sizer), Some(max_bytes)) => match sizer(&value)? {
+                bytes if bytes > max_bytes => return Err(CacheError::MaxSizeExceeded),
+                bytes => bytes,
+            },
+            _ => 0,
+        };
 
-                Ok(())
-            }
+        let mut store = match self.store.lock() {
+            Ok(store) => store,
             Err(e) => {
                 let error_msg = format!("Failed to acquire cache write lock: {}", e);
                 tracing::error!("{}", error_msg);
-                Err(CacheError::LockError(error_msg))
+                return Err(CacheError::LockError(error_msg));
             }
+        };
+
+        // The entry being replaced frees its slot and bytes first, then
+        // evict until both the count and byte budgets have room
+        let was_present = self.take(&mut store, &key).is_some();
+        let mut evicted = 0;
+        while self.over_budget(&store, bytes) && self.evict(&mut store) {
+            evicted += 1;
         }
+
+        store.insert(key.clone(), CacheEntry::new(value, ttl, bytes));
+        self.bytes.fetch_add(bytes, atomic::Ordering::Relaxed);
+        drop(store);
+
+        if was_present {
+            tracing::debug!("Updated existing Pokémon in cache: {}", key);
+        } else {
+            tracing::debug!("Inserted new Pokémon into cache: {}", key);
+        }
+        self.emit(|| CacheEvent::Insert(key));
+        self.record(|stats| {
+            stats.inserts += 1;
+            stats.removes += evicted;
+            stats.evictions += evicted;
+        });
+        Ok(())
     }
 
-    fn remove(&self, key: &str) -> Option<T> {
+    fn remove(&self, key: &str) -> Option<Arc<T>> {
         if key.is_empty() {
             tracing::warn!("Attempted to remove cache entry with empty key");
             return None;
         }
 
-        match self.store.lock() {
-            Ok(mut store) => {
-                let removed = store.remove(key).map(|entry| entry.value);
-                if removed.is_some() {
-                    tracing::debug!("Removed cache entry: {}", key);
-                    
-                    // Update stats
-                    if let Ok(mut stats) = self.stats.lock() {
-                        stats.removes += 1;
-                    }
-                }
-                removed
-            }
+        let removed = match self.store.lock() {
+            Ok(mut store) => self.take(&mut store, key).map(|entry| entry.value),
             Err(e) => {
                 tracing::error!("Failed to acquire cache write lock for removal of key {}: {}", key, e);
-                None
+                return None;
             }
+        };
+        if removed.is_some() {
+            tracing::debug!("Removed cache entry: {}", key);
+            self.record(|stats| stats.removes += 1);
         }
+        removed
     }
 
     fn clear(&self) {
-        match self.store.lock() {
+        let size = match self.store.lock() {
             Ok(mut store) => {
                 let size = store.len();
                 store.clear();
-                tracing::info!("Cleared cache ({} entries)", size);
-                
-                // Reset stats
-                if let Ok(mut stats) = self.stats.lock() {
-                    *stats = CacheStats::default();
-                }
+                self.bytes.store(0, atomic::Ordering::Relaxed);
+                size
             }
             Err(e) => {
                 tracing::error!("Failed to acquire cache write lock for clearing: {}", e);
+                return;
             }
-        }
+        };
+        tracing::info!("Cleared cache ({} entries)", size);
+        self.record(|stats| *stats = CacheStats::default());
     }
 
     fn size(&self) -> usize {
@@ -380,23 +607,78 @@ where
     fn cleanup_expired(&self) {
         self.cleanup_expired_entries();
     }
+
+    fn stats(&self) -> Option<CacheStats> {
+        InmemoryCache::stats(self)
+    }
+
+    fn keys(&self) -> Vec<String> {
+        InmemoryCache::keys(self)
+    }
+
+    fn peek(&self, key: &str) -> Option<Arc<T>> {
+        let store = self.store.lock().ok()?;
+        store
+            .get(key)
+            .filter(|entry| !entry.is_expired())
+            .map(|entry| Arc::clone(&entry.value))
+    }
+
+    fn access_counts(&self) -> Vec<(String, u64)> {
+        let Ok(store) = self.store.lock() else {
+            return Vec::new();
+        };
+        let mut counts: Vec<(String, u64)> = store
+            .iter()
+            .filter(|(_, entry)| !entry.is_expired())
+            .map(|(key, entry)| (key.clone(), entry.access_count))
+            .collect();
+        drop(store);
+
+        // Ties break on the key so the ranking is stable
+        counts.sort_by(|(a_key, a_count), (b_key, b_count)| {
+            b_count.cmp(a_count).then_with(|| natural_cmp(a_key, b_key))
+        });
+        counts
+    }
+
+    fn entry_age(&self, key: &str) -> Option<EntryAge> {
+        let store = self.store.lock().ok()?;
+        store.get(key).filter(|entry| !entry.is_expired()).map(|entry| {
+            let age = entry.created_at.elapsed();
+            EntryAge {
+                age,
+                ttl_remaining: entry.ttl.saturating_sub(age),
+            }
+        })
+    }
 }
 
-// Periodic cleanup task
-impl<T> InmemoryCache<T>
+// Periodic cleanup task for any backend, sweeping on the default
+// `cleanup_interval`
+pub async fn start_cleanup_task<T>(cache: Arc<dyn CacheTrait<T>>)
 where
-    T: Clone + Send + Sync,
+    T: Clone + Send + Sync + 'static,
 {
-    pub async fn start_cleanup_task<U>(cache: Arc<dyn CacheTrait<U>>)
-    where
-        U: Clone + Send + Sync,
-    {
-        let mut interval = tokio::time::interval(Duration::from_secs(300)); // Clean every 5 minutes
-        
-        loop {
-            interval.tick().await;
-            tracing::debug!("Starting periodic cache cleanup");
-            cache.cleanup_expired();
+    let period = Duration::from_secs(CacheConfig::default().cleanup_interval);
+    start_cleanup_task_every(cache, period).await
+}
+
+// Same sweep on a caller-chosen period (`cleanup_interval`, or the eager
+// expiration interval). Each sweep runs on the blocking pool, since some
+// backends delete files as they go.
+pub async fn start_cleanup_task_every<T>(cache: Arc<dyn CacheTrait<T>>, period: Duration)
+where
+    T: Clone + Send + Sync + 'static,
+{
+    let mut interval = tokio::time::interval(period);
+
+    loop {
+        interval.tick().await;
+        tracing::debug!("Starting periodic cache cleanup");
+        let cache = cache.clone();
+        if let Err(e) = tokio::task::spawn_blocking(move || cache.cleanup_expired()).await {
+            tracing::error!("Cache cleanup failed: {}", e);
         }
     }
 }
@@ -411,6 +693,7 @@ mod tests {
             r#type: "memory".to_string(),
             max_size: 3,
             expiration: 3600,
+            ..Default::default()
         };
         
         let cache: InmemoryCache<String> = InmemoryCache::new(config);
@@ -433,6 +716,7 @@ mod tests {
             r#type: "memory".to_string(),
             max_size: 2,
             expiration: 3600,
+            ..Default::default()
         };
         
         let cache: InmemoryCache<String> = InmemoryCache::new(config);
@@ -450,6 +734,115 @@ mod tests {
         assert!(cache.get("3").is_some());
     }
 
+    #[tokio::test(start_paused = true)]
+    async fn test_eviction_follows_recency_of_access() {
+        let config = CacheConfig {
+            r#type: "memory".to_string(),
+            max_size: 3,
+            expiration: 3600,
+            ..Default::default()
+        };
+        let cache: InmemoryCache<String> = InmemoryCache::new(config);
+
+        for key in ["1", "2", "3"] {
+            cache.insert(key.to_string(), "{}".to_string()).unwrap();
+            tokio::time::advance(Duration::from_millis(2)).await;
+        }
+        // Reading the oldest entry makes #2 the least recently used
+        assert!(cache.get("1").is_some());
+        tokio::time::advance(Duration::from_millis(2)).await;
+        cache.insert("4".to_string(), "{}".to_string()).unwrap();
+
+        assert!(!cache.contains_key("2"));
+        for key in ["1", "3", "4"] {
+            assert!(cache.contains_key(key), "{}", key);
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_eviction_policies_pick_different_victims() {
+        async fn victim(policy: &str) -> String {
+            let config = CacheConfig {
+                max_size: 3,
+                eviction_policy: policy.to_string(),
+                ..Default::default()
+            };
+            let cache: InmemoryCache<String> = InmemoryCache::new(config);
+
+            // #1 is read often but not lately; #2 and #3 are newer but never read
+            cache.insert("1".to_string(), "{}".to_string()).unwrap();
+            for _ in 0..3 {
+                cache.get("1");
+            }
+            for key in ["2", "3"] {
+                tokio::time::advance(Duration::from_millis(2)).await;
+                cache.insert(key.to_string(), "{}".to_string()).unwrap();
+            }
+            tokio::time::advance(Duration::from_millis(2)).await;
+            cache.insert("4".to_string(), "{}".to_string()).unwrap();
+
+            let mut victims: Vec<&str> = ["1", "2", "3"].into_iter().filter(|key| !cache.contains_key(key)).collect();
+            assert_eq!(victims.len(), 1);
+            victims.remove(0).to_string()
+        }
+
+        assert_eq!(victim("lru").await, "1");
+        assert_eq!(victim("lfu").await, "2");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_byte_budget_triggers_eviction() {
+        // Each value serializes to 12 bytes: ten characters plus quotes
+        let value = |c: char| c.to_string().repeat(10);
+        let config = CacheConfig {
+            max_size: 100,
+            max_bytes: Some(30),
+            ..Default::default()
+        };
+        let cache: InmemoryCache<String> = InmemoryCache::new(config).with_sizer(json_len);
+
+        cache.insert("1".to_string(), value('a')).unwrap();
+        tokio::time::advance(Duration::from_millis(2)).await;
+        cache.insert("2".to_string(), value('b')).unwrap();
+        assert_eq!(cache.byte_size(), 24);
+
+        // A third would reach 36 bytes, so the least recently used goes
+        tokio::time::advance(Duration::from_millis(2)).await;
+        cache.insert("3".to_string(), value('c')).unwrap();
+        assert_eq!(cache.byte_size(), 24);
+        assert!(!cache.contains_key("1"));
+        assert_eq!(CacheTrait::stats(&cache).unwrap().evictions, 1);
+
+        // Replacing an entry doesn't count its old bytes against the budget
+        cache.insert("3".to_string(), value('d')).unwrap();
+        assert_eq!(cache.size(), 2);
+
+        // Nothing can make room for an entry over the whole budget
+        assert!(matches!(
+            cache.insert("4".to_string(), "x".repeat(40)),
+            Err(CacheError::MaxSizeExceeded)
+        ));
+        assert_eq!(cache.size(), 2);
+
+        // The running total follows removals
+        cache.remove("3");
+        assert_eq!(cache.byte_size(), 12);
+        cache.clear();
+        assert_eq!(cache.byte_size(), 0);
+    }
+
+    #[test]
+    fn test_byte_budget_needs_a_sizer() {
+        let config = CacheConfig {
+            max_bytes: Some(1),
+            ..Default::default()
+        };
+        let cache: InmemoryCache<String> = InmemoryCache::new(config);
+        cache.insert("1".to_string(), "x".repeat(40)).unwrap();
+        assert_eq!(cache.size(), 1);
+        assert_eq!(cache.byte_size(), 0);
+    }
+
     #[test]
     fn test_invalid_operations() {
         let cache: InmemoryCache<String> = InmemoryCache::with_defaults();
@@ -465,6 +858,7 @@ mod tests {
             r#type: "memory".to_string(),
             max_size: 10,
             expiration: 3600,
+            ..Default::default()
         };
         
         let cache: InmemoryCache<String> = InmemoryCache::new(config);
@@ -474,18 +868,128 @@ mod tests {
         
         let retrieved = cache.get("key1");
         assert!(retrieved.is_some());
-        assert_eq!(retrieved.unwrap(), "value1");
+        assert_eq!(*retrieved.unwrap(), "value1");
         
         // Test cache miss
         assert!(cache.get("nonexistent").is_none());
     }
 
+    #[test]
+    fn test_get_shares_stored_value() {
+        let cache: InmemoryCache<String> = InmemoryCache::with_defaults();
+        assert!(cache.insert("/pokemon/25".to_string(), "{}".to_string()).is_ok());
+
+        // Hits hand out the same allocation rather than deep copies
+        let first = cache.get("/pokemon/25").unwrap();
+        let second = cache.get("/pokemon/25").unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+    }
+
+    #[test]
+    fn test_access_counts_ranked() {
+        let cache: InmemoryCache<String> = InmemoryCache::with_defaults();
+        for key in ["/pokemon/1", "/pokemon/25", "/pokemon/150"] {
+            assert!(cache.insert(key.to_string(), "{}".to_string()).is_ok());
+        }
+        for _ in 0..3 {
+            cache.get("/pokemon/25");
+        }
+        cache.get("/pokemon/150");
+
+        // Inserting counts as the first access
+        assert_eq!(
+            cache.access_counts(),
+            [
+                ("/pokemon/25".to_string(), 4),
+                ("/pokemon/150".to_string(), 2),
+                ("/pokemon/1".to_string(), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_event_channel() {
+        let config = CacheConfig {
+            max_size: 1,
+            ttl: HashMap::from([("pokemon-species".to_string(), 0)]),
+            event_channel_capacity: Some(16),
+            ..Default::default()
+        };
+        let cache: InmemoryCache<String> = InmemoryCache::new(config);
+        let mut events = cache.subscribe().unwrap();
+
+        cache.insert("/pokemon/25".to_string(), "{}".to_string()).unwrap();
+        cache.get("/pokemon/25");
+        cache.get("/pokemon/1");
+        cache.insert("/pokemon-species/25".to_string(), "{}".to_string()).unwrap();
+        cache.get("/pokemon-species/25");
+
+        let received: Vec<CacheEvent> = std::iter::from_fn(|| events.try_recv().ok()).collect();
+        assert_eq!(
+            received,
+            vec![
+                CacheEvent::Insert("/pokemon/25".to_string()),
+                CacheEvent::Hit("/pokemon/25".to_string()),
+                CacheEvent::Miss("/pokemon/1".to_string()),
+                CacheEvent::Evict("/pokemon/25".to_string()),
+                CacheEvent::Insert("/pokemon-species/25".to_string()),
+                CacheEvent::Expire("/pokemon-species/25".to_string()),
+                CacheEvent::Miss("/pokemon-species/25".to_string()),
+            ]
+        );
+
+        // Without a configured capacity there is nothing to subscribe to
+        assert!(InmemoryCache::<String>::with_defaults().subscribe().is_none());
+    }
+
+    #[test]
+    fn test_concurrent_access_does_not_deadlock() {
+        let cache = Arc::new(InmemoryCache::<u64>::new(CacheConfig {
+            max_size: 16,
+            ..Default::default()
+        }));
+
+        // Hammer every lock-taking path from several threads; a lock order
+        // inversion would hang them, so the test fails on a timeout instead
+        let (done_tx, done_rx) = std::sync::mpsc::channel();
+        for thread in 0..4u64 {
+            let cache = cache.clone();
+            let done_tx = done_tx.clone();
+            std::thread::spawn(move || {
+                for i in 0..2_000u64 {
+                    let key = format!("/pokemon/{}", (i * 7 + thread) % 32);
+                    cache.insert(key.clone(), i).unwrap();
+                    cache.get(&key);
+                    if i % 10 == 0 {
+                        cache.remove(&key);
+                        cache.stats();
+                        cache.hit_rate();
+                        cache.cleanup_expired();
+                    }
+                }
+                done_tx.send(()).unwrap();
+            });
+        }
+        drop(done_tx);
+
+        for _ in 0..4 {
+            done_rx
+                .recv_timeout(Duration::from_secs(30))
+                .expect("cache threads deadlocked");
+        }
+        let stats = cache.stats().unwrap();
+        assert_eq!(stats.inserts, 8_000);
+        assert_eq!(stats.hits + stats.misses, 8_000);
+        assert!(cache.size() <= 16);
+    }
+
     #[test]
     fn test_generic_number_cache() {
         let config = CacheConfig {
             r#type: "memory".to_string(),
             max_size: 5,
             expiration: 3600,
+            ..Default::default()
         };
         
         let cache: InmemoryCache<i32> = InmemoryCache::new(config);
@@ -494,8 +998,175 @@ mod tests {
         assert!(cache.insert("number1".to_string(), 42).is_ok());
         assert!(cache.insert("number2".to_string(), 100).is_ok());
         
-        assert_eq!(cache.get("number1"), Some(42));
-        assert_eq!(cache.get("number2"), Some(100));
+        assert_eq!(cache.get("number1").as_deref(), Some(&42));
+        assert_eq!(cache.get("number2").as_deref(), Some(&100));
         assert_eq!(cache.get("nonexistent"), None);
     }
+
+    #[test]
+    fn test_cache_presized_to_max_size() {
+        let config = CacheConfig {
+            r#type: "memory".to_string(),
+            max_size: 500,
+            expiration: 3600,
+            ..Default::default()
+        };
+
+        let cache: InmemoryCache<String> = InmemoryCache::new(config);
+        assert!(cache.store.lock().unwrap().capacity() >= 500);
+
+        // Huge configured sizes only pre-allocate up to the cap
+        let config = CacheConfig {
+            r#type: "memory".to_string(),
+            max_size: u32::MAX,
+            expiration: 3600,
+            ..Default::default()
+        };
+
+        let cache: InmemoryCache<String> = InmemoryCache::new(config);
+        let capacity = cache.store.lock().unwrap().capacity();
+        assert!(capacity >= MAX_PRESIZE_CAPACITY);
+        assert!(capacity < 2 * MAX_PRESIZE_CAPACITY);
+    }
+
+    #[test]
+    fn test_absurd_max_size_is_clamped() {
+        let config = CacheConfig {
+            max_size: u32::MAX,
+            ..Default::default()
+        };
+        let ceiling = config.max_size_ceiling;
+
+        let cache: InmemoryCache<String> = InmemoryCache::new(config);
+        assert_eq!(cache.config().max_size, ceiling);
+
+        let config = CacheConfig {
+            max_size: 10,
+            max_size_ceiling: 5,
+            ..Default::default()
+        };
+        let cache: InmemoryCache<String> = InmemoryCache::new(config);
+        assert_eq!(cache.config().max_size, 5);
+    }
+
+    #[test]
+    fn test_keys_sorted_is_numeric_aware() {
+        let cache: InmemoryCache<String> = InmemoryCache::with_defaults();
+
+        for key in ["/pokemon/10", "/pokemon/2", "/pokemon/100", "/pokemon/1", "/type/3", "25", "3"] {
+            assert!(cache.insert(key.to_string(), "{}".to_string()).is_ok());
+        }
+
+        assert_eq!(
+            cache.keys_sorted(),
+            vec!["/pokemon/1", "/pokemon/2", "/pokemon/10", "/pokemon/100", "/type/3", "3", "25"]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_per_kind_ttl() {
+        let config = CacheConfig {
+            r#type: "memory".to_string(),
+            max_size: 10,
+            expiration: 3600,
+            ttl: HashMap::from([
+                ("pokemon".to_string(), 0),
+                ("pokemon-species".to_string(), 86400),
+            ]),
+            ..Default::default()
+        };
+
+        let cache: InmemoryCache<String> = InmemoryCache::new(config);
+        assert!(cache.insert("/pokemon/25".to_string(), "{}".to_string()).is_ok());
+        assert!(cache.insert("/pokemon-species/25".to_string(), "{}".to_string()).is_ok());
+        assert!(cache.insert("/type/13".to_string(), "{}".to_string()).is_ok());
+
+        tokio::time::advance(Duration::from_millis(5)).await;
+
+        // Zero TTL for pokemon expires immediately; species and the default kind survive
+        assert!(cache.get("/pokemon/25").is_none());
+        assert!(cache.get("/pokemon-species/25").is_some());
+        assert!(cache.get("/type/13").is_some());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_insert_with_ttl_overrides_config() {
+        let cache: InmemoryCache<String> = InmemoryCache::with_defaults();
+        let short = Duration::from_millis(50);
+        let long = Duration::from_millis(300);
+        cache.insert_with_ttl("/pokemon/1".to_string(), "{}".to_string(), short).unwrap();
+        cache.insert_with_ttl("/pokemon/4".to_string(), "{}".to_string(), long).unwrap();
+        cache.insert("/pokemon/7".to_string(), "{}".to_string()).unwrap();
+
+        tokio::time::advance(Duration::from_millis(100)).await;
+        assert!(cache.get("/pokemon/1").is_none());
+        assert!(cache.get("/pokemon/4").is_some());
+
+        tokio::time::advance(Duration::from_millis(250)).await;
+        CacheTrait::cleanup_expired(&cache);
+        // Only the config default (an hour) outlives both overrides
+        assert_eq!(cache.keys(), vec!["/pokemon/7".to_string()]);
+    }
+
+    #[cfg(feature = "no-debug-logs")]
+    #[test]
+    fn test_debug_logs_compiled_out() {
+        use tracing::level_filters::{LevelFilter, STATIC_MAX_LEVEL};
+        assert_eq!(STATIC_MAX_LEVEL, LevelFilter::INFO);
+    }
+
+    #[test]
+    fn test_resource_kind() {
+        assert_eq!(resource_kind("/pokemon/25"), "pokemon");
+        assert_eq!(resource_kind("/pokemon-species/25"), "pokemon-species");
+        assert_eq!(resource_kind("species:25"), "species");
+        assert_eq!(resource_kind("25"), "25");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_eager_sweep_removes_expired_without_access() {
+        let config = CacheConfig {
+            max_size: 10,
+            ttl: HashMap::from([("pokemon".to_string(), 0)]),
+            eager_expiration: true,
+            eager_expiration_interval_ms: 10,
+            ..Default::default()
+        };
+        let interval = Duration::from_millis(config.eager_expiration_interval_ms);
+
+        let cache: Arc<dyn CacheTrait<String>> = Arc::new(InmemoryCache::new(config));
+        assert!(cache.insert("/pokemon/25".to_string(), "{}".to_string()).is_ok());
+        assert!(cache.insert("/type/13".to_string(), "{}".to_string()).is_ok());
+        assert_eq!(cache.size(), 2);
+
+        let sweeper = tokio::spawn(start_cleanup_task_every(cache.clone(), interval));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        sweeper.abort();
+
+        // The expired entry is gone from the store even though nothing read it
+        assert_eq!(cache.size(), 1);
+        assert!(cache.get("/type/13").is_some());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_cleanup_tick_purges_expired() {
+        let config = CacheConfig {
+            ttl: HashMap::from([("pokemon".to_string(), 0)]),
+            cleanup_interval: 1,
+            ..Default::default()
+        };
+        let period = Duration::from_secs(config.cleanup_interval);
+
+        let cache: Arc<dyn CacheTrait<String>> = Arc::new(InmemoryCache::new(config));
+        cache.insert("/pokemon/25".to_string(), "{}".to_string()).unwrap();
+        tokio::time::advance(Duration::from_millis(1)).await;
+
+        // The first tick fires as soon as the task starts
+        let sweeper = tokio::spawn(start_cleanup_task_every(cache.clone(), period));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        sweeper.abort();
+
+        assert_eq!(cache.size(), 0);
+        assert_eq!(cache.stats().unwrap().cleanups, 1);
+    }
 }
@@ -0,0 +1,159 @@
+use crate::cache::{CacheError, CacheEvent, CacheStats, CacheTrait, EntryAge, InmemoryCache};
+use crate::config::CacheConfig;
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+use serde::{de::DeserializeOwned, Serialize};
+use std::io::{Read, Write};
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+// In-memory cache holding each value as deflate-compressed JSON. Saves memory
+// on large documents at the cost of compressing on insert and inflating on
+// every hit, so hits return a fresh Arc rather than a shared one.
+pub struct CompressedCache<T> {
+    inner: InmemoryCache<Vec<u8>>,
+    _value: PhantomData<fn() -> T>,
+}
+
+impl<T> CompressedCache<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    pub fn new(config: CacheConfig) -> Self {
+        Self {
+            // The budget counts the compressed bytes actually held
+            inner: InmemoryCache::new(config).with_sizer(|bytes| Ok(bytes.len())),
+            _value: PhantomData,
+        }
+    }
+
+    // Cache events from the underlying store, see `InmemoryCache::subscribe`
+    pub fn subscribe(&self) -> Option<broadcast::Receiver<CacheEvent>> {
+        self.inner.subscribe()
+    }
+
+    // Total compressed bytes currently held, for measuring the savings
+    pub fn stored_bytes(&self) -> usize {
+        self.inner
+            .keys()
+            .iter()
+            .filter_map(|key| self.inner.peek(key))
+            .map(|bytes| bytes.len())
+            .sum()
+    }
+
+    fn compress(value: &T) -> Result<Vec<u8>, CacheError> {
+        let json = serde_json::to_vec(value).map_err(|e| CacheError::Serialization(e.to_string()))?;
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all(&json)
+            .and_then(|_| encoder.finish())
+            .map_err(|e| CacheError::Serialization(e.to_string()))
+    }
+
+    fn decompress(key: &str, bytes: &[u8]) -> Option<Arc<T>> {
+        let mut json = Vec::new();
+        let decoded = DeflateDecoder::new(bytes)
+            .read_to_end(&mut json)
+            .map_err(|e| e.to_string())
+            .and_then(|_| serde_json::from_slice(&json).map_err(|e| e.to_string()));
+        match decoded {
+            Ok(value) => Some(Arc::new(value)),
+            Err(e) => {
+                tracing::error!("Failed to decompress cache entry for key {}: {}", key, e);
+                None
+            }
+        }
+    }
+}
+
+impl<T> CacheTrait<T> for CompressedCache<T>
+where
+    T: Clone + Send + Sync + Serialize + DeserializeOwned,
+{
+    fn get(&self, key: &str) -> Option<Arc<T>> {
+        let bytes = self.inner.get(key)?;
+        Self::decompress(key, &bytes)
+    }
+
+    fn insert(&self, key: String, value: T) -> Result<(), CacheError> {
+        self.inner.insert(key, Self::compress(&value)?)
+    }
+
+    fn insert_with_ttl(&self, key: String, value: T, ttl: Duration) -> Result<(), CacheError> {
+        self.inner.insert_with_ttl(key, Self::compress(&value)?, ttl)
+    }
+
+    fn remove(&self, key: &str) -> Option<Arc<T>> {
+        let bytes = self.inner.remove(key)?;
+        Self::decompress(key, &bytes)
+    }
+
+    fn clear(&self) {
+        self.inner.clear()
+    }
+
+    fn size(&self) -> usize {
+        self.inner.size()
+    }
+
+    fn hit_rate(&self) -> f64 {
+        self.inner.hit_rate()
+    }
+
+    fn cleanup_expired(&self) {
+        self.inner.cleanup_expired()
+    }
+
+    fn stats(&self) -> Option<CacheStats> {
+        CacheTrait::stats(&self.inner)
+    }
+
+    fn keys(&self) -> Vec<String> {
+        self.inner.keys()
+    }
+
+    fn peek(&self, key: &str) -> Option<Arc<T>> {
+        let bytes = self.inner.peek(key)?;
+        Self::decompress(key, &bytes)
+    }
+
+    fn entry_age(&self, key: &str) -> Option<EntryAge> {
+        self.inner.entry_age(key)
+    }
+
+    fn access_counts(&self) -> Vec<(String, u64)> {
+        self.inner.access_counts()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::upstream::UpstreamResponse;
+
+    fn large_body() -> String {
+        let moves: Vec<String> = (0..500)
+            .map(|i| format!(r#"{{"move": {{"name": "move-{}", "url": "https://pokeapi.co/api/v2/move/{}/"}}}}"#, i, i))
+            .collect();
+        format!(r#"{{"id": 25, "name": "pikachu", "moves": [{}]}}"#, moves.join(","))
+    }
+
+    #[test]
+    fn test_compressed_round_trip() {
+        let cache: CompressedCache<UpstreamResponse> = CompressedCache::new(CacheConfig::default());
+        let response = UpstreamResponse {
+            body: large_body(),
+            content_type: Some("application/json".to_string()),
+        };
+
+        assert!(cache.insert("/pokemon/25".to_string(), response.clone()).is_ok());
+        assert_eq!(cache.get("/pokemon/25").as_deref(), Some(&response));
+        assert_eq!(cache.peek("/pokemon/25").as_deref(), Some(&response));
+        assert!(cache.get("/pokemon/1").is_none());
+
+        // The repetitive JSON shrinks to a fraction of its size
+        assert!(cache.stored_bytes() * 4 < response.body.len());
+    }
+}
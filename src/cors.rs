@@ -0,0 +1,86 @@
+use crate::config::CorsConfig;
+use axum::http::{HeaderName, HeaderValue, Method};
+use tower_http::cors::{AllowHeaders, AllowOrigin, Any, CorsLayer};
+
+// The CORS layer for `config`, or `None` when no origins are allowed. Fails on
+// origins, methods or headers that aren't valid HTTP values.
+pub fn cors_layer(config: &CorsConfig) -> Result<Option<CorsLayer>, String> {
+    if config.allowed_origins.is_empty() {
+        return Ok(None);
+    }
+
+    let origins = if is_wildcard(&config.allowed_origins) {
+        AllowOrigin::any()
+    } else {
+        let origins = config
+            .allowed_origins
+            .iter()
+            .map(|origin| {
+                HeaderValue::from_str(origin).map_err(|_| format!("cors.allowed_origins: invalid origin {:?}", origin))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        AllowOrigin::list(origins)
+    };
+
+    let methods = config
+        .allowed_methods
+        .iter()
+        .map(|method| {
+            method
+                .to_uppercase()
+                .parse::<Method>()
+                .map_err(|_| format!("cors.allowed_methods: invalid method {:?}", method))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let headers = if is_wildcard(&config.allowed_headers) {
+        AllowHeaders::from(Any)
+    } else {
+        let headers = config
+            .allowed_headers
+            .iter()
+            .map(|name| {
+                name.parse::<HeaderName>()
+                    .map_err(|_| format!("cors.allowed_headers: invalid header {:?}", name))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        AllowHeaders::list(headers)
+    };
+
+    Ok(Some(
+        CorsLayer::new()
+            .allow_origin(origins)
+            .allow_methods(methods)
+            .allow_headers(headers),
+    ))
+}
+
+fn is_wildcard(values: &[String]) -> bool {
+    values.iter().any(|value| value == "*")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cors_layer_validates_config() {
+        assert!(cors_layer(&CorsConfig::default()).unwrap().is_none());
+
+        let config = CorsConfig {
+            allowed_origins: vec!["*".to_string()],
+            ..Default::default()
+        };
+        assert!(cors_layer(&config).unwrap().is_some());
+
+        let config = CorsConfig {
+            allowed_origins: vec!["https://example.com".to_string()],
+            allowed_methods: vec!["GET".to_string(), "not a method".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            cors_layer(&config).unwrap_err(),
+            r#"cors.allowed_methods: invalid method "not a method""#
+        );
+    }
+}
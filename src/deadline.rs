@@ -0,0 +1,69 @@
+use crate::error::error_response;
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{HeaderName, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const REQUEST_DEADLINE_HEADER: HeaderName = HeaderName::from_static("x-request-deadline");
+
+// Values at or above this are absolute Unix timestamps in ms (September 2001
+// onwards); anything smaller is a relative budget in ms
+const ABSOLUTE_DEADLINE_THRESHOLD_MS: u64 = 1_000_000_000_000;
+
+// Time left before a client deadline, or `None` when the header is malformed.
+// Deadlines already in the past leave no time at all.
+pub fn parse_deadline(value: &str, now: SystemTime) -> Option<Duration> {
+    let ms: u64 = value.trim().parse().ok()?;
+    if ms < ABSOLUTE_DEADLINE_THRESHOLD_MS {
+        return Some(Duration::from_millis(ms));
+    }
+
+    let now_ms = now.duration_since(UNIX_EPOCH).ok()?.as_millis() as u64;
+    Some(Duration::from_millis(ms.saturating_sub(now_ms)))
+}
+
+// Middleware bounding a request by the client's `X-Request-Deadline`, capped at
+// the server's own limit. Requests that overrun get 504 Gateway Timeout.
+pub async fn enforce_request_deadline(
+    State(max_deadline): State<Duration>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let Some(deadline) = request
+        .headers()
+        .get(&REQUEST_DEADLINE_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| parse_deadline(value, SystemTime::now()))
+    else {
+        return next.run(request).await;
+    };
+
+    let deadline = deadline.min(max_deadline);
+    match tokio::time::timeout(deadline, next.run(request)).await {
+        Ok(response) => response,
+        Err(_) => {
+            tracing::warn!("Request exceeded its client deadline of {:?}", deadline);
+            error_response(StatusCode::GATEWAY_TIMEOUT, "Request deadline exceeded")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_deadline() {
+        let now = UNIX_EPOCH + Duration::from_millis(1_700_000_000_000);
+
+        assert_eq!(parse_deadline("250", now), Some(Duration::from_millis(250)));
+        assert_eq!(parse_deadline("1700000000750", now), Some(Duration::from_millis(750)));
+        assert_eq!(parse_deadline("1699999999000", now), Some(Duration::ZERO));
+        assert_eq!(parse_deadline("soon", now), None);
+        assert_eq!(parse_deadline("-5", now), None);
+    }
+}
@@ -0,0 +1,507 @@
+use crate::async_cache::{unblock, AsyncCacheTrait};
+use crate::cache::{json_len, ttl_for_key, CacheError, CacheStats, CacheTrait, EntryAge, InmemoryCache};
+use crate::config::CacheConfig;
+use crate::etag::fnv1a;
+use futures::future::BoxFuture;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// Extension of entry files; anything else in the directory is left alone
+const ENTRY_EXTENSION: &str = "json";
+// Extension of files being written; ones left by a crash are deleted on load
+const PARTIAL_EXTENSION: &str = "tmp";
+
+// Numbers each write's temporary file, so concurrent writers never share one
+static WRITE_SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+// One entry as written to disk. The expiry is wall-clock time so it still
+// means something after a restart. Written from borrowed fields, read back
+// as owned ones.
+#[derive(Serialize, Deserialize)]
+struct DiskEntry<K, T> {
+    key: K,
+    expires_at_ms: u64,
+    value: T,
+}
+
+// In-memory cache that writes every insert through to a JSON file under
+// `cache.disk_path`, and reloads the unexpired files on startup so a restart
+// doesn't begin cold. Reads are served from memory. The file of an entry
+// memory evicts or expires is deleted after the insert or sweep that dropped
+// it. Through `AsyncCacheTrait` the file IO runs on the blocking pool.
+pub struct DiskCache<T>
+where
+    T: Clone + Send + Sync,
+{
+    inner: InmemoryCache<T>,
+    dir: PathBuf,
+    // Keys memory dropped on its own whose files are still to be deleted
+    discarded: Arc<Mutex<Vec<String>>>,
+}
+
+impl<T> DiskCache<T>
+where
+    T: Clone + Send + Sync + Serialize + DeserializeOwned,
+{
+    // Open (creating if needed) `config.disk_path` and load its unexpired
+    // entries. Fails when the path is unset or the directory can't be created.
+    pub fn new(config: CacheConfig) -> Result<Self, CacheError> {
+        let dir = config
+            .disk_path
+            .clone()
+            .map(PathBuf::from)
+            .ok_or_else(|| CacheError::Backend("cache.disk_path is required for the disk cache".to_string()))?;
+        fs::create_dir_all(&dir).map_err(|e| backend_error(&dir, e))?;
+
+        let discarded = Arc::new(Mutex::new(Vec::new()));
+        let inner = InmemoryCache::new(config).with_sizer(json_len).with_discard_listener({
+            let discarded = discarded.clone();
+            move |key: &str| {
+                if let Ok(mut discarded) = discarded.lock() {
+                    discarded.push(key.to_string());
+                }
+            }
+        });
+        let cache = Self { inner, dir, discarded };
+        let loaded = cache.load()?;
+        tracing::info!("Loaded {} cache entries from {}", loaded, cache.dir.display());
+        Ok(cache)
+    }
+
+    // Insert every unexpired entry file into memory, deleting expired and
+    // unreadable ones, and any over the memory budget
+    fn load(&self) -> Result<usize, CacheError> {
+        for path in files_with_extension(&self.dir, PARTIAL_EXTENSION)? {
+            remove_file(&path);
+        }
+
+        let now = now_ms();
+        let mut loaded: usize = 0;
+        for path in entry_files(&self.dir)? {
+            let entry = fs::read(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|bytes| serde_json::from_slice::<DiskEntry<String, T>>(&bytes).map_err(|e| e.to_string()));
+            let entry = match entry {
+                Ok(entry) if entry.expires_at_ms > now => entry,
+                Ok(_) => {
+                    remove_file(&path);
+                    continue;
+                }
+                Err(e) => {
+                    tracing::warn!("Discarding unreadable cache file {}: {}", path.display(), e);
+                    remove_file(&path);
+                    continue;
+                }
+            };
+
+            let ttl = Duration::from_millis(entry.expires_at_ms - now);
+            match self.inner.insert_with_ttl(entry.key, entry.value, ttl) {
+                Ok(()) => loaded += 1,
+                Err(e) => {
+                    tracing::warn!("Failed to load cache file {}: {}", path.display(), e);
+                    remove_file(&path);
+                }
+            }
+        }
+        let evicted = self.take_discarded();
+        loaded = loaded.saturating_sub(evicted.len());
+        evicted.iter().for_each(|path| remove_file(path));
+        Ok(loaded)
+    }
+
+    // The file contents for an entry, checked before anything is written
+    fn encode(key: &str, value: &T, ttl: Duration) -> Result<Vec<u8>, CacheError> {
+        if key.is_empty() {
+            return Err(CacheError::InvalidKey("Key cannot be empty".to_string()));
+        }
+        let entry = DiskEntry {
+            key,
+            expires_at_ms: now_ms().saturating_add(ttl.as_millis() as u64),
+            value,
+        };
+        serde_json::to_vec(&entry).map_err(|e| CacheError::Serialization(e.to_string()))
+    }
+}
+
+impl<T> DiskCache<T>
+where
+    T: Clone + Send + Sync,
+{
+    // Keys may be far longer than a file name may be and hold characters one
+    // can't, so the name is a hash of the key. FNV is fixed by its spec, so
+    // names stay stable across restarts and upgrades. The file holds the key
+    // itself, so a collision can only cost one of the entries its file.
+    fn path_for(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{:016x}.{}", fnv1a(key.as_bytes()), ENTRY_EXTENSION))
+    }
+
+    // Files of the entries memory dropped since the last call. A key that
+    // was inserted again since is skipped, so its new file survives.
+    fn take_discarded(&self) -> Vec<PathBuf> {
+        let keys = match self.discarded.lock() {
+            Ok(mut discarded) => std::mem::take(&mut *discarded),
+            Err(_) => return Vec::new(),
+        };
+        keys.iter()
+            .filter(|key| !self.inner.contains_key(key))
+            .map(|key| self.path_for(key))
+            .collect()
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since_epoch| since_epoch.as_millis() as u64)
+}
+
+fn remove_file(path: &Path) {
+    if let Err(e) = fs::remove_file(path)
+        && e.kind() != std::io::ErrorKind::NotFound
+    {
+        tracing::warn!("Failed to delete cache file {}: {}", path.display(), e);
+    }
+}
+
+fn backend_error(path: &Path, err: std::io::Error) -> CacheError {
+    CacheError::Backend(format!("{}: {}", path.display(), err))
+}
+
+fn entry_files(dir: &Path) -> Result<Vec<PathBuf>, CacheError> {
+    files_with_extension(dir, ENTRY_EXTENSION)
+}
+
+fn files_with_extension(dir: &Path, extension: &str) -> Result<Vec<PathBuf>, CacheError> {
+    let entries = fs::read_dir(dir).map_err(|e| backend_error(dir, e))?;
+    Ok(entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|found| found == extension))
+        .collect())
+}
+
+// Write through a temporary file of its own and rename it, so a crash
+// mid-write never leaves a truncated entry behind and concurrent writers of
+// one key never interleave
+fn write_file(path: &Path, bytes: &[u8]) -> Result<(), CacheError> {
+    let sequence = WRITE_SEQUENCE.fetch_add(1, Ordering::Relaxed);
+    let partial = path.with_extension(format!("{}.{}", sequence, PARTIAL_EXTENSION));
+    fs::write(&partial, bytes)
+        .and_then(|_| fs::rename(&partial, path))
+        .map_err(|e| {
+            remove_file(&partial);
+            backend_error(path, e)
+        })
+}
+
+fn remove_all(dir: &Path) {
+    match entry_files(dir) {
+        Ok(paths) => paths.iter().for_each(|path| remove_file(path)),
+        Err(e) => tracing::error!("Failed to clear disk cache: {}", e),
+    }
+}
+
+impl<T> CacheTrait<T> for DiskCache<T>
+where
+    T: Clone + Send + Sync + Serialize + DeserializeOwned,
+{
+    fn get(&self, key: &str) -> Option<Arc<T>> {
+        self.inner.get(key)
+    }
+
+    fn insert(&self, key: String, value: T) -> Result<(), CacheError> {
+        let ttl = ttl_for_key(self.inner.config(), &key);
+        self.insert_with_ttl(key, value, ttl)
+    }
+
+    // Memory first, so an entry memory refuses never gets a file, and the
+    // evictions it causes are cleaned up right after
+    fn insert_with_ttl(&self, key: String, value: T, ttl: Duration) -> Result<(), CacheError> {
+        let bytes = Self::encode(&key, &value, ttl)?;
+        let path = self.path_for(&key);
+        self.inner.insert_with_ttl(key.clone(), value, ttl)?;
+        if let Err(e) = write_file(&path, &bytes) {
+            self.inner.remove(&key);
+            return Err(e);
+        }
+        self.take_discarded().iter().for_each(|path| remove_file(path));
+        Ok(())
+    }
+
+    fn remove(&self, key: &str) -> Option<Arc<T>> {
+        remove_file(&self.path_for(key));
+        self.inner.remove(key)
+    }
+
+    fn clear(&self) {
+        self.inner.clear();
+        remove_all(&self.dir);
+    }
+
+    fn size(&self) -> usize {
+        self.inner.size()
+    }
+
+    fn hit_rate(&self) -> f64 {
+        self.inner.hit_rate()
+    }
+
+    // Sweep memory, then delete the files of whatever it dropped
+    fn cleanup_expired(&self) {
+        self.inner.cleanup_expired();
+        self.take_discarded().iter().for_each(|path| remove_file(path));
+    }
+
+    fn stats(&self) -> Option<CacheStats> {
+        CacheTrait::stats(&self.inner)
+    }
+
+    fn keys(&self) -> Vec<String> {
+        self.inner.keys()
+    }
+
+    fn peek(&self, key: &str) -> Option<Arc<T>> {
+        self.inner.peek(key)
+    }
+
+    fn entry_age(&self, key: &str) -> Option<EntryAge> {
+        self.inner.entry_age(key)
+    }
+
+    fn access_counts(&self) -> Vec<(String, u64)> {
+        self.inner.access_counts()
+    }
+}
+
+// Reads are served from memory; only the file IO is moved off the runtime
+impl<T> AsyncCacheTrait<T> for DiskCache<T>
+where
+    T: Clone + Send + Sync + Serialize + DeserializeOwned + 'static,
+{
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<Arc<T>>> {
+        Box::pin(std::future::ready(self.inner.get(key)))
+    }
+
+    fn insert(&self, key: String, value: T) -> BoxFuture<'_, Result<(), CacheError>> {
+        Box::pin(async move {
+            let ttl = ttl_for_key(self.inner.config(), &key);
+            let bytes = Self::encode(&key, &value, ttl)?;
+            let path = self.path_for(&key);
+            self.inner.insert_with_ttl(key.clone(), value, ttl)?;
+            let discarded = self.take_discarded();
+            let written = unblock(move || {
+                discarded.iter().for_each(|path| remove_file(path));
+                write_file(&path, &bytes)
+            })
+            .await
+            .and_then(|written| written);
+            if written.is_err() {
+                self.inner.remove(&key);
+            }
+            written
+        })
+    }
+
+    fn remove<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<Arc<T>>> {
+        Box::pin(async move {
+            let path = self.path_for(key);
+            if let Err(e) = unblock(move || remove_file(&path)).await {
+                tracing::error!("Failed to delete cache file for key {}: {}", key, e);
+            }
+            self.inner.remove(key)
+        })
+    }
+
+    fn clear(&self) -> BoxFuture<'_, ()> {
+        Box::pin(async move {
+            self.inner.clear();
+            let dir = self.dir.clone();
+            if let Err(e) = unblock(move || remove_all(&dir)).await {
+                tracing::error!("Failed to clear disk cache: {}", e);
+            }
+        })
+    }
+
+    fn size(&self) -> BoxFuture<'_, usize> {
+        Box::pin(std::future::ready(self.inner.size()))
+    }
+
+    fn keys(&self) -> BoxFuture<'_, Vec<String>> {
+        Box::pin(std::future::ready(self.inner.keys()))
+    }
+
+    fn peek<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<Arc<T>>> {
+        Box::pin(std::future::ready(self.inner.peek(key)))
+    }
+
+    fn entry_age<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<EntryAge>> {
+        Box::pin(std::future::ready(self.inner.entry_age(key)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Not the whole parent: with both cache traits in scope `insert` and
+    // friends would be ambiguous
+    use super::{entry_files, CacheConfig, CacheError, CacheTrait, DiskCache};
+    use crate::async_cache;
+    use std::fs;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    // A fresh directory per test, so parallel tests don't share files
+    fn disk_config(name: &str) -> CacheConfig {
+        let dir = std::env::temp_dir().join(format!("disk-cache-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        CacheConfig {
+            disk_path: Some(dir.to_string_lossy().into_owned()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_entries_survive_reopening() {
+        let config = disk_config("reopen");
+        let cache = DiskCache::<String>::new(config.clone()).unwrap();
+        cache.insert("/pokemon/25".to_string(), "pikachu".to_string()).unwrap();
+        cache.insert("/pokemon/1".to_string(), "bulbasaur".to_string()).unwrap();
+        cache.remove("/pokemon/1");
+        drop(cache);
+
+        let cache = DiskCache::<String>::new(config.clone()).unwrap();
+        assert_eq!(cache.get("/pokemon/25").as_deref().map(String::as_str), Some("pikachu"));
+        assert_eq!(cache.get("/pokemon/1"), None);
+        assert_eq!(cache.size(), 1);
+
+        cache.clear();
+        drop(cache);
+        assert_eq!(DiskCache::<String>::new(config.clone()).unwrap().size(), 0);
+
+        fs::remove_dir_all(config.disk_path.unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_expired_entries_skipped_on_load() {
+        let config = disk_config("expired");
+        let cache = DiskCache::<String>::new(config.clone()).unwrap();
+        cache
+            .insert_with_ttl("/pokemon/25".to_string(), "pikachu".to_string(), Duration::from_millis(50))
+            .unwrap();
+        cache.insert("/pokemon/1".to_string(), "bulbasaur".to_string()).unwrap();
+        drop(cache);
+
+        std::thread::sleep(Duration::from_millis(100));
+        let cache = DiskCache::<String>::new(config.clone()).unwrap();
+        assert_eq!(cache.get("/pokemon/25"), None);
+        assert_eq!(cache.get("/pokemon/1").as_deref().map(String::as_str), Some("bulbasaur"));
+        // The expired file was deleted rather than skipped again next time
+        assert_eq!(entry_files(&cache.dir).unwrap().len(), 1);
+
+        fs::remove_dir_all(config.disk_path.unwrap()).unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_files_of_dropped_entries_are_deleted() {
+        let config = CacheConfig {
+            max_size: 2,
+            ..disk_config("dropped")
+        };
+        let cache = DiskCache::<String>::new(config.clone()).unwrap();
+        cache
+            .insert_with_ttl("/pokemon/25".to_string(), "pikachu".to_string(), Duration::from_millis(50))
+            .unwrap();
+        tokio::time::advance(Duration::from_millis(1)).await;
+        cache.insert("/pokemon/1".to_string(), "bulbasaur".to_string()).unwrap();
+
+        // An eviction deletes the file as part of the insert that caused it
+        tokio::time::advance(Duration::from_millis(1)).await;
+        cache.insert("/pokemon/4".to_string(), "charmander".to_string()).unwrap();
+        assert!(!cache.path_for("/pokemon/25").exists());
+        assert_eq!(entry_files(&cache.dir).unwrap().len(), 2);
+
+        // An expiry, at the next sweep
+        cache
+            .insert_with_ttl("/pokemon/7".to_string(), "squirtle".to_string(), Duration::from_millis(50))
+            .unwrap();
+        tokio::time::advance(Duration::from_millis(100)).await;
+        cache.cleanup_expired();
+        assert!(!cache.path_for("/pokemon/7").exists());
+        assert_eq!(entry_files(&cache.dir).unwrap(), [cache.path_for("/pokemon/4")]);
+
+        fs::remove_dir_all(config.disk_path.unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_long_keys_get_short_file_names() {
+        let config = disk_config("long-keys");
+        let cache = DiskCache::<String>::new(config.clone()).unwrap();
+        let key = format!("/pokemon/25?{}", "x".repeat(4096));
+        cache.insert(key.clone(), "pikachu".to_string()).unwrap();
+        assert_eq!(cache.path_for(&key).file_name().unwrap().len(), 21);
+        drop(cache);
+
+        let cache = DiskCache::<String>::new(config.clone()).unwrap();
+        assert_eq!(cache.get(&key).as_deref().map(String::as_str), Some("pikachu"));
+
+        fs::remove_dir_all(config.disk_path.unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_concurrent_writers_of_one_key() {
+        let config = disk_config("concurrent");
+        let cache = Arc::new(DiskCache::<String>::new(config.clone()).unwrap());
+        let writers: Vec<_> = (0..8)
+            .map(|n| {
+                let cache = cache.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..20 {
+                        cache.insert("/pokemon/25".to_string(), format!("pikachu-{}", n)).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        // One complete entry file and no temporary ones left behind
+        let files: Vec<_> = fs::read_dir(&cache.dir).unwrap().map(|entry| entry.unwrap().path()).collect();
+        assert_eq!(files, [cache.path_for("/pokemon/25")]);
+        drop(cache);
+        assert_eq!(DiskCache::<String>::new(config.clone()).unwrap().size(), 1);
+
+        fs::remove_dir_all(config.disk_path.unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_async_writes_reach_disk() {
+        let config = disk_config("async");
+        let cache = DiskCache::<String>::new(config.clone()).unwrap();
+        async_cache::AsyncCacheTrait::insert(&cache, "/pokemon/25".to_string(), "pikachu".to_string()).await.unwrap();
+        async_cache::AsyncCacheTrait::insert(&cache, "/pokemon/1".to_string(), "bulbasaur".to_string()).await.unwrap();
+        async_cache::AsyncCacheTrait::remove(&cache, "/pokemon/1").await;
+        assert_eq!(entry_files(&cache.dir).unwrap(), [cache.path_for("/pokemon/25")]);
+        drop(cache);
+
+        let cache = DiskCache::<String>::new(config.clone()).unwrap();
+        assert_eq!(
+            async_cache::AsyncCacheTrait::get(&cache, "/pokemon/25").await.as_deref().map(String::as_str),
+            Some("pikachu")
+        );
+        async_cache::AsyncCacheTrait::clear(&cache).await;
+        assert!(entry_files(&cache.dir).unwrap().is_empty());
+
+        fs::remove_dir_all(config.disk_path.unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_new_requires_disk_path() {
+        assert!(matches!(
+            DiskCache::<String>::new(CacheConfig::default()),
+            Err(CacheError::Backend(_))
+        ));
+    }
+}
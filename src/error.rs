@@ -0,0 +1,175 @@
+use crate::cache::CacheError;
+use axum::{
+    body::Body,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+
+// Custom error types for better error handling
+#[derive(Debug, Clone)]
+pub enum AppError {
+    ConfigError(String),
+    NetworkError(String),
+    CacheError(String),
+    ParseError(String),
+    // The upstream has no Pokémon with this id
+    NotFound(u32),
+    // The endpoint reads the cache, but `pokemon.cache_enabled` is off
+    CacheDisabled,
+    // Non-success upstream response, kept so passthrough can relay it
+    UpstreamStatus {
+        status: StatusCode,
+        headers: Vec<(header::HeaderName, header::HeaderValue)>,
+    },
+}
+
+// Upstream headers relayed to the client alongside a non-success status
+pub const RELAYED_UPSTREAM_HEADERS: [header::HeaderName; 1] = [header::RETRY_AFTER];
+
+impl std::fmt::Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AppError::ConfigError(msg) => write!(f, "Configuration error: {}", msg),
+            AppError::NetworkError(msg) => write!(f, "Network error: {}", msg),
+            AppError::CacheError(msg) => write!(f, "Cache error: {}", msg),
+            AppError::ParseError(msg) => write!(f, "Parse error: {}", msg),
+            AppError::NotFound(id) => write!(f, "Pokémon {} not found", id),
+            AppError::CacheDisabled => write!(f, "Cache is disabled"),
+            AppError::UpstreamStatus { status, .. } => {
+                write!(f, "Upstream error: status {}", status)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<reqwest::Error> for AppError {
+    fn from(err: reqwest::Error) -> Self {
+        AppError::NetworkError(err.to_string())
+    }
+}
+
+impl From<toml::de::Error> for AppError {
+    fn from(err: toml::de::Error) -> Self {
+        AppError::ConfigError(err.to_string())
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(err: serde_json::Error) -> Self {
+        AppError::ParseError(err.to_string())
+    }
+}
+
+impl From<CacheError> for AppError {
+    fn from(err: CacheError) -> Self {
+        AppError::CacheError(err.to_string())
+    }
+}
+
+// The JSON body of every error response
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub error: String,
+    pub code: u16,
+}
+
+// Not-found, disabled-cache and upstream statuses are told to the client;
+// anything else is our failure and is reported as a bare 500 (details stay in
+// the logs)
+impl AppError {
+    // Status and message shown to the client
+    pub fn public_status(&self) -> (StatusCode, String) {
+        match self {
+            AppError::NotFound(id) => (StatusCode::NOT_FOUND, format!("Pokémon {} not found", id)),
+            AppError::CacheDisabled => (StatusCode::SERVICE_UNAVAILABLE, "Cache is disabled".to_string()),
+            AppError::UpstreamStatus { status, .. } => (*status, format!("Upstream returned {}", status)),
+            AppError::ConfigError(_)
+            | AppError::NetworkError(_)
+            | AppError::CacheError(_)
+            | AppError::ParseError(_) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error".to_string())
+            }
+        }
+    }
+
+    // Our own failure, as opposed to one the client or upstream caused
+    pub fn is_internal(&self) -> bool {
+        matches!(
+            self,
+            AppError::ConfigError(_) | AppError::NetworkError(_) | AppError::CacheError(_) | AppError::ParseError(_)
+        )
+    }
+}
+
+// Handlers return `Result<_, AppError>` and use `?`; internal failures are
+// logged here, once, with the request's span
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        if self.is_internal() {
+            tracing::error!("Request failed: {}", self);
+        }
+        let (status, message) = self.public_status();
+        let mut response = error_response(status, message);
+        if let AppError::UpstreamStatus { headers, .. } = self {
+            response.headers_mut().extend(headers);
+        }
+        response
+    }
+}
+
+// Build a JSON response with an explicit Content-Length so small bodies are
+// never sent with chunked transfer encoding
+pub fn json_response(status: StatusCode, body: String) -> Response {
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::CONTENT_LENGTH, body.len())
+        .body(Body::from(body))
+        .unwrap()
+}
+
+// `value` as a 200 JSON response
+pub fn json_ok(value: &impl Serialize) -> Result<Response, AppError> {
+    Ok(json_response(StatusCode::OK, serde_json::to_string(value)?))
+}
+
+pub fn error_response(status: StatusCode, message: impl Into<String>) -> Response {
+    let body = ErrorResponse {
+        error: message.into(),
+        code: status.as_u16(),
+    };
+    // Serializing two plain fields cannot fail
+    json_response(status, serde_json::to_string(&body).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_error_converts_to_cache_variant() {
+        let error = AppError::from(CacheError::LockError("poisoned".to_string()));
+        assert!(matches!(&error, AppError::CacheError(msg) if msg == "Cache lock error: poisoned"));
+        assert_eq!(error.to_string(), "Cache error: Cache lock error: poisoned");
+        // Cache failures are ours, so the client only sees a bare 500
+        assert_eq!(
+            error.public_status(),
+            (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error".to_string())
+        );
+
+        let error: AppError = CacheError::MaxSizeExceeded.into();
+        assert!(matches!(error, AppError::CacheError(_)));
+    }
+
+    #[test]
+    fn test_upstream_status_not_labelled_network_error() {
+        let error = AppError::UpstreamStatus {
+            status: StatusCode::BAD_GATEWAY,
+            headers: Vec::new(),
+        };
+        assert_eq!(error.to_string(), "Upstream error: status 502 Bad Gateway");
+    }
+}
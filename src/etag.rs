@@ -0,0 +1,95 @@
+use crate::error::error_response;
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+
+// Tag for a response body: its length and 64-bit FNV-1a hash. FNV is fixed by
+// its spec, so tags stay valid across restarts and upgrades. Weak, because the
+// compression layer may re-encode the body after this one.
+pub fn etag_for(body: &[u8]) -> String {
+    format!("W/\"{:x}-{:016x}\"", body.len(), fnv1a(body))
+}
+
+// 64-bit FNV-1a
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+// Whether an `If-None-Match` value names `etag`: `*`, or any tag in the list
+// when compared weakly (ignoring `W/`)
+pub fn matches_if_none_match(if_none_match: &str, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    let etag = opaque(etag);
+    if_none_match.trim() == "*" || if_none_match.split(',').any(|tag| opaque(tag) == etag)
+}
+
+// Middleware adding an `ETag` to successful GET responses and answering a
+// matching `If-None-Match` with an empty 304. Only bodies with a
+// Content-Length are tagged; streamed ones (the cache dump) pass through.
+pub async fn etag(request: Request<Body>, next: Next) -> Response {
+    if request.method() != Method::GET {
+        return next.run(request).await;
+    }
+    let if_none_match = request.headers().get(header::IF_NONE_MATCH).cloned();
+
+    let response = next.run(request).await;
+    if response.status() != StatusCode::OK || !response.headers().contains_key(header::CONTENT_LENGTH) {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::error!("Failed to read response body for ETag: {}", e);
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, "Internal server error");
+        }
+    };
+    let etag = etag_for(&bytes);
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        parts.headers.insert(header::ETAG, value);
+    }
+
+    if if_none_match
+        .as_ref()
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| matches_if_none_match(value, &etag))
+    {
+        parts.status = StatusCode::NOT_MODIFIED;
+        strip_content_headers(&mut parts.headers);
+        return Response::from_parts(parts, Body::empty());
+    }
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+// A 304 describes the client's copy, so it carries no body headers
+fn strip_content_headers(headers: &mut HeaderMap) {
+    headers.remove(header::CONTENT_LENGTH);
+    headers.remove(header::CONTENT_TYPE);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_etag_matching() {
+        let etag = etag_for(br#"{"id": 25}"#);
+        assert_eq!(etag, etag_for(br#"{"id": 25}"#));
+        assert_ne!(etag, etag_for(br#"{"id": 26}"#));
+        assert!(etag.starts_with("W/\"a-"));
+
+        assert!(matches_if_none_match(&etag, &etag));
+        assert!(matches_if_none_match(etag.trim_start_matches("W/"), &etag));
+        assert!(matches_if_none_match(&format!("\"stale\", {}", etag), &etag));
+        assert!(matches_if_none_match(" * ", &etag));
+        assert!(!matches_if_none_match("\"stale\"", &etag));
+        assert!(!matches_if_none_match("", &etag));
+    }
+}
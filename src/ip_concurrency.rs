@@ -0,0 +1,211 @@
+use crate::error::error_response;
+use crate::rate_limit::client_ip;
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::Response,
+};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+
+// Tracks in-flight requests per client IP and caps how many a single client may hold
+#[derive(Debug)]
+pub struct IpConcurrencyLimiter {
+    max_per_ip: usize,
+    in_flight: Mutex<HashMap<IpAddr, usize>>,
+    // Key clients by the last `X-Forwarded-For` address, as `rate_limit`
+    // does with the same setting
+    trust_forwarded_for: bool,
+}
+
+impl IpConcurrencyLimiter {
+    pub fn new(max_per_ip: usize, trust_forwarded_for: bool) -> Self {
+        Self {
+            max_per_ip,
+            in_flight: Mutex::new(HashMap::new()),
+            trust_forwarded_for,
+        }
+    }
+
+    // Reserve a slot for `ip`, returning a guard that releases it on drop, or
+    // `Err` when the client is at its cap. A poisoned map shouldn't take the
+    // proxy down with it, so the request goes through uncounted.
+    fn try_acquire(self: &Arc<Self>, ip: IpAddr) -> Result<Option<InFlightSlot>, ()> {
+        let Ok(mut in_flight) = self.in_flight.lock() else {
+            return Ok(None);
+        };
+        let count = in_flight.entry(ip).or_insert(0);
+        if *count >= self.max_per_ip {
+            return Err(());
+        }
+        *count += 1;
+
+        Ok(Some(InFlightSlot {
+            limiter: self.clone(),
+            ip,
+        }))
+    }
+
+    // Number of requests currently in flight for `ip`
+    pub fn in_flight(&self, ip: IpAddr) -> usize {
+        self.in_flight
+            .lock()
+            .map(|in_flight| in_flight.get(&ip).copied().unwrap_or(0))
+            .unwrap_or(0)
+    }
+
+    fn release(&self, ip: IpAddr) {
+        if let Ok(mut in_flight) = self.in_flight.lock()
+            && let Some(count) = in_flight.get_mut(&ip)
+        {
+            *count -= 1;
+            if *count == 0 {
+                in_flight.remove(&ip);
+            }
+        }
+    }
+}
+
+// Releases the client's slot when dropped, so it fires on success, error and cancellation
+struct InFlightSlot {
+    limiter: Arc<IpConcurrencyLimiter>,
+    ip: IpAddr,
+}
+
+impl Drop for InFlightSlot {
+    fn drop(&mut self) {
+        self.limiter.release(self.ip);
+    }
+}
+
+// Middleware rejecting requests with 429 when the client already has too many in flight
+pub async fn limit_ip_concurrency(
+    State(limiter): State<Arc<IpConcurrencyLimiter>>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let Some(ip) = client_ip(&request, limiter.trust_forwarded_for) else {
+        return next.run(request).await;
+    };
+
+    match limiter.try_acquire(ip) {
+        Ok(_slot) => next.run(request).await,
+        Err(()) => {
+            tracing::warn!("Rejecting request from {}: too many concurrent requests", ip);
+            error_response(StatusCode::TOO_MANY_REQUESTS, "Too many concurrent requests")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::ConnectInfo, middleware, routing::get, Router};
+    use std::net::SocketAddr;
+    use std::time::Duration;
+    use tokio::sync::Semaphore;
+    use tower::ServiceExt;
+
+    fn request_from(ip: [u8; 4]) -> Request<Body> {
+        let mut request = Request::get("/slow").body(Body::empty()).unwrap();
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from((ip, 4000))));
+        request
+    }
+
+    // Through a proxy at 10.0.0.9 that appended `ip` to X-Forwarded-For
+    fn forwarded_request(ip: &str) -> Request<Body> {
+        let mut request = request_from([10, 0, 0, 9]);
+        request.headers_mut().insert("x-forwarded-for", ip.parse().unwrap());
+        request
+    }
+
+    // A `/slow` route whose requests stay in flight until `release` gets permits
+    fn slow_app(limiter: Arc<IpConcurrencyLimiter>, release: Arc<Semaphore>) -> Router {
+        Router::new()
+            .route(
+                "/slow",
+                get(move || {
+                    let release = release.clone();
+                    async move {
+                        let _permit = release.acquire().await.unwrap();
+                        "done"
+                    }
+                }),
+            )
+            .layer(middleware::from_fn_with_state(limiter, limit_ip_concurrency))
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_cap_per_ip() {
+        let release = Arc::new(Semaphore::new(0));
+        let limiter = Arc::new(IpConcurrencyLimiter::new(2, false));
+        let app = slow_app(limiter.clone(), release.clone());
+
+        // Two requests fill the client's budget and stay in flight
+        let first = tokio::spawn(app.clone().oneshot(request_from([10, 0, 0, 1])));
+        let second = tokio::spawn(app.clone().oneshot(request_from([10, 0, 0, 1])));
+        let ip = IpAddr::from([10, 0, 0, 1]);
+        while limiter.in_flight(ip) < 2 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        // A third from the same IP is rejected, another IP is unaffected
+        let rejected = app.clone().oneshot(request_from([10, 0, 0, 1])).await.unwrap();
+        assert_eq!(rejected.status(), StatusCode::TOO_MANY_REQUESTS);
+        let body = axum::body::to_bytes(rejected.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], br#"{"error":"Too many concurrent requests","code":429}"#);
+
+        release.add_permits(3);
+        let other = app.clone().oneshot(request_from([10, 0, 0, 2])).await.unwrap();
+        assert_eq!(other.status(), StatusCode::OK);
+
+        assert_eq!(first.await.unwrap().unwrap().status(), StatusCode::OK);
+        assert_eq!(second.await.unwrap().unwrap().status(), StatusCode::OK);
+
+        // Slots are released once the requests complete
+        assert_eq!(limiter.in_flight(ip), 0);
+    }
+    #[tokio::test]
+    async fn test_forwarded_clients_have_their_own_caps() {
+        let release = Arc::new(Semaphore::new(0));
+        let limiter = Arc::new(IpConcurrencyLimiter::new(1, true));
+        let app = slow_app(limiter.clone(), release.clone());
+
+        // Both arrive from the proxy's address but count against their own caps
+        let first = tokio::spawn(app.clone().oneshot(forwarded_request("203.0.113.7")));
+        while limiter.in_flight(IpAddr::from([203, 0, 113, 7])) < 1 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        let second = tokio::spawn(app.clone().oneshot(forwarded_request("203.0.113.8")));
+        while limiter.in_flight(IpAddr::from([203, 0, 113, 8])) < 1 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        let rejected = app.clone().oneshot(forwarded_request("203.0.113.7")).await.unwrap();
+        assert_eq!(rejected.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        release.add_permits(2);
+        assert_eq!(first.await.unwrap().unwrap().status(), StatusCode::OK);
+        assert_eq!(second.await.unwrap().unwrap().status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn test_poisoned_limiter_fails_open() {
+        let limiter = Arc::new(IpConcurrencyLimiter::new(1, false));
+        let poisoner = limiter.clone();
+        let _ = std::thread::spawn(move || {
+            let _in_flight = poisoner.in_flight.lock().unwrap();
+            panic!("poison the map");
+        })
+        .join();
+
+        // Requests go through uncounted rather than being rejected forever
+        let ip = IpAddr::from([10, 0, 0, 1]);
+        let _first = limiter.try_acquire(ip).unwrap();
+        let _second = limiter.try_acquire(ip).unwrap();
+    }
+}
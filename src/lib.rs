@@ -1,5 +1,26 @@
+pub mod access_log;
+pub mod admin;
+pub mod advice;
+pub mod async_cache;
 pub mod cache;
+pub mod compressed_cache;
 pub mod config;
+pub mod cors;
+pub mod deadline;
+pub mod disk_cache;
+pub mod error;
+pub mod etag;
+pub mod ip_concurrency;
+pub mod pokemon;
+#[cfg(feature = "metrics")]
+pub mod prometheus;
+pub mod rate_limit;
+#[cfg(feature = "redis")]
+pub mod redis_cache;
+pub mod request_id;
+pub mod server_timing;
+pub mod stats_history;
+pub mod upstream;
 
 pub use cache::*;
 pub use config::*;
@@ -0,0 +1,873 @@
+use crate::config::{IdMappingConfig, IdSpec, PokemonConfig};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+// Highest national dex id served by the upstream
+pub const MAX_POKEMON_ID: u32 = 1025;
+
+// How many Pokémon a single comparison may include
+pub const MIN_COMPARE_IDS: usize = 2;
+pub const MAX_COMPARE_IDS: usize = 6;
+
+// The types `/types/{type}` lists; the upstream's `unknown`, `shadow` and
+// `stellar` have no regular members
+pub const POKEMON_TYPES: [&str; 18] = [
+    "normal", "fighting", "flying", "poison", "ground", "rock", "bug", "ghost", "steel",
+    "fire", "water", "grass", "electric", "psychic", "ice", "dragon", "dark", "fairy",
+];
+
+// Check whether a proxied path addresses a single Pokémon document
+// (e.g. `/pokemon/25` or `/pokemon/pikachu`, but not `/pokemon/25/encounters`)
+pub fn is_pokemon_path(path: &str) -> bool {
+    path.strip_prefix("/pokemon/")
+        .is_some_and(|rest| !rest.is_empty() && !rest.contains('/'))
+}
+
+// Numeric id of a single Pokémon path (`/pokemon/25`), `None` for names
+pub fn pokemon_path_id(path: &str) -> Option<u32> {
+    path.strip_prefix("/pokemon/")?.parse().ok()
+}
+
+// Apply the configured response caps to a Pokémon document. Bodies are
+// returned untouched when no cap is configured or the body isn't a JSON object.
+pub fn shape_pokemon_body<'a>(config: &PokemonConfig, body: &'a str) -> Cow<'a, str> {
+    if config.max_moves_in_response.is_none() && config.max_forms_in_response.is_none() {
+        return Cow::Borrowed(body);
+    }
+
+    let mut pokemon: Value = match serde_json::from_str(body) {
+        Ok(Value::Object(map)) => Value::Object(map),
+        Ok(_) | Err(_) => return Cow::Borrowed(body),
+    };
+
+    if let Some(max_moves) = config.max_moves_in_response {
+        let truncated = truncate_list(&mut pokemon, "moves", max_moves);
+        pokemon["moves_truncated"] = Value::Bool(truncated);
+    }
+    if let Some(max_forms) = config.max_forms_in_response {
+        let truncated = truncate_list(&mut pokemon, "forms", max_forms);
+        pokemon["forms_truncated"] = Value::Bool(truncated);
+    }
+
+    reserialized(&pokemon, body)
+}
+
+// Drop `moves` (and its truncation flag) from a Pokémon document; most of a
+// document's size is its move list
+pub fn strip_moves(body: &str) -> Cow<'_, str> {
+    let mut pokemon = match serde_json::from_str::<Value>(body) {
+        Ok(Value::Object(map)) => map,
+        Ok(_) | Err(_) => return Cow::Borrowed(body),
+    };
+    if pokemon.remove("moves").is_none() {
+        return Cow::Borrowed(body);
+    }
+    pokemon.remove("moves_truncated");
+
+    reserialized(&pokemon, body)
+}
+
+// Top-level fields of a PokéAPI Pokémon document, the ones `?fields=` may select
+pub const POKEMON_FIELDS: &[&str] = &[
+    "abilities",
+    "base_experience",
+    "cries",
+    "forms",
+    "game_indices",
+    "height",
+    "held_items",
+    "id",
+    "is_default",
+    "location_area_encounters",
+    "moves",
+    "name",
+    "order",
+    "past_abilities",
+    "past_types",
+    "species",
+    "sprites",
+    "stats",
+    "types",
+    "weight",
+];
+
+// Parse a comma-separated field list like "id,name,types", rejecting names
+// that aren't Pokémon fields
+pub fn parse_fields(fields: &str) -> Result<Vec<String>, String> {
+    let fields: Vec<String> = fields
+        .split(',')
+        .map(str::trim)
+        .filter(|field| !field.is_empty())
+        .map(str::to_string)
+        .collect();
+    if fields.is_empty() {
+        return Err("fields must name at least one field".to_string());
+    }
+    if let Some(unknown) = fields.iter().find(|field| !POKEMON_FIELDS.contains(&field.as_str())) {
+        return Err(format!(
+            "unknown field {:?}; expected one of {}",
+            unknown,
+            POKEMON_FIELDS.join(", ")
+        ));
+    }
+    Ok(fields)
+}
+
+// Keep only `fields` (and their truncation flags) of a Pokémon document.
+// Bodies that aren't JSON objects, or that have no other fields, are
+// returned untouched.
+pub fn select_fields<'a>(body: &'a str, fields: &[String]) -> Cow<'a, str> {
+    let pokemon = match serde_json::from_str::<Value>(body) {
+        Ok(Value::Object(map)) => map,
+        Ok(_) | Err(_) => return Cow::Borrowed(body),
+    };
+    let total = pokemon.len();
+    let selected: serde_json::Map<String, Value> = pokemon
+        .into_iter()
+        .filter(|(key, _)| {
+            let field = key.strip_suffix("_truncated").unwrap_or(key);
+            fields.iter().any(|wanted| wanted == field)
+        })
+        .collect();
+    if selected.len() == total {
+        return Cow::Borrowed(body);
+    }
+
+    reserialized(&selected, body)
+}
+
+// A changed document as JSON, falling back to the original body should it
+// somehow fail to serialize
+fn reserialized<'a>(document: &impl Serialize, body: &'a str) -> Cow<'a, str> {
+    serde_json::to_string(document).map_or(Cow::Borrowed(body), Cow::Owned)
+}
+
+// Truncate an array field in place, returning whether anything was dropped
+fn truncate_list(pokemon: &mut Value, field: &str, max: usize) -> bool {
+    match pokemon.get_mut(field).and_then(Value::as_array_mut) {
+        Some(list) if list.len() > max => {
+            list.truncate(max);
+            true
+        }
+        _ => false,
+    }
+}
+
+// The fields of a Pokémon document needed for a side-by-side comparison
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PokemonSummary {
+    pub id: u64,
+    pub name: String,
+    pub types: Vec<String>,
+    pub height: u64,
+    pub weight: u64,
+    pub stats: BTreeMap<String, i64>,
+}
+
+impl PokemonSummary {
+    // Extract a summary from a raw PokéAPI Pokémon document
+    pub fn from_json(pokemon: &Value) -> Option<Self> {
+        let stats = pokemon
+            .get("stats")?
+            .as_array()?
+            .iter()
+            .map(|stat| {
+                Some((
+                    stat.get("stat")?.get("name")?.as_str()?.to_string(),
+                    stat.get("base_stat")?.as_i64()?,
+                ))
+            })
+            .collect::<Option<BTreeMap<_, _>>>()?;
+        let types = pokemon
+            .get("types")?
+            .as_array()?
+            .iter()
+            .map(|slot| Some(slot.get("type")?.get("name")?.as_str()?.to_string()))
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(Self {
+            id: pokemon.get("id")?.as_u64()?,
+            name: pokemon.get("name")?.as_str()?.to_string(),
+            types,
+            height: pokemon.get("height")?.as_u64()?,
+            weight: pokemon.get("weight")?.as_u64()?,
+            stats,
+        })
+    }
+}
+
+// A Pokémon document together with the cache state it was served from
+#[derive(Debug, Serialize)]
+pub struct CachedPokemon {
+    pub pokemon: Value,
+    pub from_cache: bool,
+    pub age_secs: u64,
+    pub ttl_remaining_secs: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PokemonComparison {
+    pub pokemon: Vec<PokemonSummary>,
+    // Per stat, each Pokémon's base stat minus the first Pokémon's
+    pub stat_deltas: BTreeMap<String, Vec<i64>>,
+}
+
+// Build a side-by-side comparison with stat deltas relative to the first Pokémon
+pub fn compare_pokemon(pokemon: Vec<PokemonSummary>) -> PokemonComparison {
+    let mut stat_deltas = BTreeMap::new();
+
+    if let Some(baseline) = pokemon.first() {
+        for (stat, base) in &baseline.stats {
+            let deltas = pokemon
+                .iter()
+                .map(|other| other.stats.get(stat).copied().unwrap_or(0) - base)
+                .collect();
+            stat_deltas.insert(stat.clone(), deltas);
+        }
+    }
+
+    PokemonComparison {
+        pokemon,
+        stat_deltas,
+    }
+}
+
+// Species-level data that isn't on the Pokémon document, picked from a raw
+// PokéAPI `/pokemon-species/{id}` document
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PokemonSpecies {
+    pub id: u32,
+    pub name: String,
+    // English genus, e.g. "Mouse Pokémon"
+    pub genus: Option<String>,
+    // First English flavor text, with the upstream's line breaks and form
+    // feeds collapsed to single spaces
+    pub flavor_text: Option<String>,
+    pub color: String,
+    pub habitat: Option<String>,
+    pub generation: String,
+    pub evolution_chain_url: Option<String>,
+    pub is_legendary: bool,
+    pub is_mythical: bool,
+}
+
+impl PokemonSpecies {
+    pub fn from_json(species: &Value) -> Option<Self> {
+        Some(Self {
+            id: species.get("id")?.as_u64()?.try_into().ok()?,
+            name: species.get("name")?.as_str()?.to_string(),
+            genus: english_entry(species.get("genera")?, "genus"),
+            flavor_text: english_entry(species.get("flavor_text_entries")?, "flavor_text")
+                .map(|text| text.split_whitespace().collect::<Vec<_>>().join(" ")),
+            color: species.get("color")?.get("name")?.as_str()?.to_string(),
+            habitat: named_resource(species.get("habitat")),
+            generation: species.get("generation")?.get("name")?.as_str()?.to_string(),
+            evolution_chain_url: species
+                .get("evolution_chain")
+                .and_then(|chain| chain.get("url")?.as_str())
+                .map(str::to_string),
+            is_legendary: species.get("is_legendary")?.as_bool()?,
+            is_mythical: species.get("is_mythical")?.as_bool()?,
+        })
+    }
+}
+
+// `field` of the first English entry in a list of localized entries
+fn english_entry(entries: &Value, field: &str) -> Option<String> {
+    entries
+        .as_array()?
+        .iter()
+        .find(|entry| entry.get("language").and_then(|language| language.get("name")?.as_str()) == Some("en"))?
+        .get(field)?
+        .as_str()
+        .map(str::to_string)
+}
+
+// A Pokémon listed under a type
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TypeMember {
+    pub name: String,
+    // Parsed from the resource URL; `None` if the URL doesn't end in an id
+    pub id: Option<u32>,
+}
+
+// The Pokémon of a raw PokéAPI `/type/{name}` document, in upstream order
+pub fn type_members(document: &Value) -> Option<Vec<TypeMember>> {
+    document
+        .get("pokemon")?
+        .as_array()?
+        .iter()
+        .map(|slot| {
+            let pokemon = slot.get("pokemon")?;
+            Some(TypeMember {
+                name: pokemon.get("name")?.as_str()?.to_string(),
+                id: pokemon.get("url").and_then(Value::as_str).and_then(resource_url_id),
+            })
+        })
+        .collect()
+}
+
+// Trailing id of a resource URL, e.g. 25 for "https://pokeapi.co/api/v2/pokemon/25/"
+fn resource_url_id(url: &str) -> Option<u32> {
+    url.trim_end_matches('/').rsplit('/').next()?.parse().ok()
+}
+
+// Name of an optional `{"name", "url"}` reference; PokéAPI sends `null` when absent
+fn named_resource(resource: Option<&Value>) -> Option<String> {
+    resource?.get("name")?.as_str().map(str::to_string)
+}
+
+// Pick `count` distinct ids from 1..=MAX_POKEMON_ID (capped at the dex size)
+#[cfg(feature = "random")]
+pub fn sample_distinct_ids(count: usize) -> Vec<u32> {
+    let count = count.min(MAX_POKEMON_ID as usize);
+    rand::seq::index::sample(&mut rand::rng(), MAX_POKEMON_ID as usize, count)
+        .into_iter()
+        .map(|index| index as u32 + 1)
+        .collect()
+}
+
+// Pick `count` distinct ids from `pool` (capped at the pool size)
+#[cfg(feature = "random")]
+pub fn sample_distinct_from(pool: &[u32], count: usize) -> Vec<u32> {
+    let count = count.min(pool.len());
+    rand::seq::index::sample(&mut rand::rng(), pool.len(), count)
+        .into_iter()
+        .map(|index| pool[index])
+        .collect()
+}
+
+// Widest id range the random endpoints will enumerate
+#[cfg(feature = "random")]
+const MAX_RANDOM_RANGE: u32 = 1_000_000;
+
+// Ids the random endpoints pick from: `random_min..=random_max` without
+// `random_exclude`, narrowed to the allowlist when there is one
+#[cfg(feature = "random")]
+pub fn random_pool(config: &PokemonConfig, allowed: Option<&AllowedIds>) -> Result<Vec<u32>, String> {
+    let (min, max) = (config.random_min, config.random_max);
+    if min > max {
+        return Err(format!("pokemon.random_min {} is above random_max {}", min, max));
+    }
+    if max - min >= MAX_RANDOM_RANGE {
+        return Err(format!(
+            "pokemon.random_min..random_max spans more than {} ids",
+            MAX_RANDOM_RANGE
+        ));
+    }
+
+    let excluded: HashSet<u32> = config.random_exclude.iter().copied().collect();
+    let pool: Vec<u32> = (min..=max)
+        .filter(|id| !excluded.contains(id) && allowed.is_none_or(|allowed| allowed.contains(*id)))
+        .collect();
+    if pool.is_empty() {
+        return Err(format!(
+            "no ids left for the random endpoints in {}-{} after exclusions and allowed_ids",
+            min, max
+        ));
+    }
+    Ok(pool)
+}
+
+// The sorted set of ids a curated deployment may serve
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AllowedIds(Vec<u32>);
+
+impl AllowedIds {
+    // Expand and validate the configured entries; every id must be in 1..=MAX_POKEMON_ID
+    pub fn from_specs(specs: &[IdSpec]) -> Result<Self, String> {
+        let mut ids = Vec::new();
+        for spec in specs {
+            let (first, last) = match spec {
+                IdSpec::Id(id) => (*id, *id),
+                IdSpec::Range(range) => {
+                    let (first, last) = range
+                        .split_once('-')
+                        .and_then(|(first, last)| Some((first.trim().parse().ok()?, last.trim().parse().ok()?)))
+                        .ok_or_else(|| format!("invalid allowed_ids range {:?} (expected \"first-last\")", range))?;
+                    if first > last {
+                        return Err(format!("invalid allowed_ids range {:?}: first id is after last", range));
+                    }
+                    (first, last)
+                }
+            };
+            if first == 0 || last > MAX_POKEMON_ID {
+                return Err(format!("allowed_ids entry {:?} is outside 1-{}", spec, MAX_POKEMON_ID));
+            }
+            ids.extend(first..=last);
+        }
+        if ids.is_empty() {
+            return Err("allowed_ids must not be empty".to_string());
+        }
+
+        ids.sort_unstable();
+        ids.dedup();
+        Ok(Self(ids))
+    }
+
+    pub fn contains(&self, id: u32) -> bool {
+        self.0.binary_search(&id).is_ok()
+    }
+
+    pub fn ids(&self) -> &[u32] {
+        &self.0
+    }
+}
+
+// Two-way translation between client ids and a renumbered upstream's ids
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdMapping {
+    offset: i64,
+    to_upstream: HashMap<u32, u32>,
+    to_client: HashMap<u32, u32>,
+}
+
+impl IdMapping {
+    // Validate that every client id maps to a positive upstream id and that no
+    // two client ids share one, so responses can be mapped back
+    pub fn from_config(config: &IdMappingConfig) -> Result<Self, String> {
+        let mut to_upstream = HashMap::new();
+        for (client, upstream) in &config.map {
+            let client: u32 = client
+                .trim()
+                .parse()
+                .ok()
+                .filter(|id| *id >= 1)
+                .ok_or_else(|| format!("invalid id_mapping key {:?} (expected a positive id)", client))?;
+            if *upstream == 0 {
+                return Err(format!("id_mapping maps {} to invalid upstream id 0", client));
+            }
+            to_upstream.insert(client, *upstream);
+        }
+
+        let mapping = Self {
+            offset: config.offset,
+            to_upstream,
+            to_client: HashMap::new(),
+        };
+        let mut to_client = HashMap::new();
+        for client in (1..=MAX_POKEMON_ID).chain(mapping.to_upstream.keys().copied()) {
+            let upstream = mapping
+                .upstream_id(client)
+                .ok_or_else(|| format!("id_mapping offset {} moves id {} out of range", config.offset, client))?;
+            if let Some(other) = to_client.insert(upstream, client).filter(|other| *other != client) {
+                return Err(format!("id_mapping sends both {} and {} to upstream id {}", other, client, upstream));
+            }
+        }
+
+        Ok(Self { to_client, ..mapping })
+    }
+
+    pub fn upstream_id(&self, client: u32) -> Option<u32> {
+        match self.to_upstream.get(&client) {
+            Some(upstream) => Some(*upstream),
+            None => u32::try_from(client as i64 + self.offset).ok().filter(|id| *id >= 1),
+        }
+    }
+
+    pub fn client_id(&self, upstream: u32) -> Option<u32> {
+        self.to_client.get(&upstream).copied()
+    }
+
+    // Upstream path for a client path; only numeric `/pokemon/{id}` paths change
+    pub fn upstream_path(&self, path: &str) -> String {
+        match pokemon_path_id(path).and_then(|id| self.upstream_id(id)) {
+            Some(upstream) => format!("/pokemon/{}", upstream),
+            None => path.to_string(),
+        }
+    }
+
+    // Rewrite a fetched document's `id` back to the client's numbering
+    pub fn to_client_body(&self, body: String) -> String {
+        let mut pokemon: Value = match serde_json::from_str(&body) {
+            Ok(Value::Object(map)) => Value::Object(map),
+            Ok(_) | Err(_) => return body,
+        };
+        let client = pokemon
+            .get("id")
+            .and_then(Value::as_u64)
+            .and_then(|id| u32::try_from(id).ok())
+            .and_then(|id| self.client_id(id));
+        match client {
+            Some(client) => {
+                pokemon["id"] = Value::from(client);
+                serde_json::to_string(&pokemon).unwrap_or(body)
+            }
+            None => body,
+        }
+    }
+}
+
+// Order for responses aggregating several Pokémon
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PokemonSort {
+    #[default]
+    Id,
+    Name,
+}
+
+// Sort raw Pokémon documents so response order doesn't depend on fetch
+// completion order. Documents missing the sort field go last.
+pub fn sort_pokemon_bodies<B: AsRef<str>>(bodies: &mut [B], sort: PokemonSort) {
+    let field = |body: &str, name: &str| {
+        serde_json::from_str::<Value>(body)
+            .ok()
+            .and_then(|pokemon| pokemon.get(name).cloned())
+    };
+    match sort {
+        PokemonSort::Id => bodies.sort_by_cached_key(|body| {
+            field(body.as_ref(), "id").and_then(|id| id.as_u64()).unwrap_or(u64::MAX)
+        }),
+        PokemonSort::Name => bodies.sort_by_cached_key(|body| {
+            let name = field(body.as_ref(), "name").and_then(|name| name.as_str().map(str::to_string));
+            (name.is_none(), name)
+        }),
+    }
+}
+
+// Drop repeated ids, keeping the first occurrence of each in request order
+pub fn dedupe_ids(ids: Vec<u32>) -> Vec<u32> {
+    let mut seen = HashSet::with_capacity(ids.len());
+    ids.into_iter().filter(|id| seen.insert(*id)).collect()
+}
+
+// Parse a comma-separated id list like "25,6", enforcing the comparison bounds.
+// With `dedupe`, repeated ids are collapsed before the bounds are checked.
+pub fn parse_compare_ids(ids: &str, dedupe: bool) -> Result<Vec<u32>, String> {
+    let ids = ids
+        .split(',')
+        .map(|id| {
+            let id = id.trim();
+            id.parse::<u32>()
+                .ok()
+                .filter(|id| (1..=MAX_POKEMON_ID).contains(id))
+                .ok_or_else(|| format!("invalid Pokémon id: {:?} (expected 1-{})", id, MAX_POKEMON_ID))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let ids = if dedupe { dedupe_ids(ids) } else { ids };
+
+    if !(MIN_COMPARE_IDS..=MAX_COMPARE_IDS).contains(&ids.len()) {
+        return Err(format!(
+            "expected between {} and {} ids, got {}",
+            MIN_COMPARE_IDS,
+            MAX_COMPARE_IDS,
+            ids.len()
+        ));
+    }
+
+    Ok(ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(max_moves: Option<usize>, max_forms: Option<usize>) -> PokemonConfig {
+        PokemonConfig {
+            api_url: "https://pokeapi.co/api/v2".to_string(),
+            timeout: 30,
+            cache_enabled: true,
+            max_moves_in_response: max_moves,
+            max_forms_in_response: max_forms,
+            include_moves_by_default: true,
+            upstream_username: None,
+            upstream_password: None,
+            passthrough_content_type_override: None,
+            allowed_ids: None,
+            id_mapping: None,
+            random_min: 1,
+            random_max: MAX_POKEMON_ID,
+            random_exclude: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_fields_projection() {
+        assert_eq!(parse_fields(" id, name ,types").unwrap(), vec!["id", "name", "types"]);
+        assert!(parse_fields("id,nickname").unwrap_err().starts_with(r#"unknown field "nickname""#));
+        assert!(parse_fields(",").is_err());
+
+        let body = r#"{"id": 25, "name": "pikachu", "moves": [1, 2], "moves_truncated": true, "weight": 60}"#;
+        let selected: Value =
+            serde_json::from_str(&select_fields(body, &parse_fields("name,moves").unwrap())).unwrap();
+        assert_eq!(
+            selected,
+            serde_json::json!({ "name": "pikachu", "moves": [1, 2], "moves_truncated": true })
+        );
+    }
+
+    #[test]
+    fn test_species_from_json() {
+        // Trimmed from PokéAPI's /pokemon-species/25
+        let mut species = serde_json::json!({
+            "id": 25,
+            "name": "pikachu",
+            "color": { "name": "yellow", "url": "https://pokeapi.co/api/v2/pokemon-color/10/" },
+            "evolution_chain": { "url": "https://pokeapi.co/api/v2/evolution-chain/10/" },
+            "flavor_text_entries": [
+                {
+                    "flavor_text": "Quand plusieurs de ces POKéMON se réunissent,\nleur énergie peut déclencher des orages.",
+                    "language": { "name": "fr", "url": "https://pokeapi.co/api/v2/language/5/" }
+                },
+                {
+                    "flavor_text": "When several of\nthese POKéMON gather, their\u{c}electricity could\nbuild and cause\nlightning storms.",
+                    "language": { "name": "en", "url": "https://pokeapi.co/api/v2/language/9/" }
+                }
+            ],
+            "genera": [
+                { "genus": "ネズミポケモン", "language": { "name": "ja", "url": "https://pokeapi.co/api/v2/language/11/" } },
+                { "genus": "Mouse Pokémon", "language": { "name": "en", "url": "https://pokeapi.co/api/v2/language/9/" } }
+            ],
+            "generation": { "name": "generation-i", "url": "https://pokeapi.co/api/v2/generation/1/" },
+            "habitat": { "name": "forest", "url": "https://pokeapi.co/api/v2/pokemon-habitat/2/" },
+            "is_legendary": false,
+            "is_mythical": false
+        });
+
+        assert_eq!(
+            PokemonSpecies::from_json(&species).unwrap(),
+            PokemonSpecies {
+                id: 25,
+                name: "pikachu".to_string(),
+                genus: Some("Mouse Pokémon".to_string()),
+                flavor_text: Some(
+                    "When several of these POKéMON gather, their electricity could build and cause lightning storms."
+                        .to_string()
+                ),
+                color: "yellow".to_string(),
+                habitat: Some("forest".to_string()),
+                generation: "generation-i".to_string(),
+                evolution_chain_url: Some("https://pokeapi.co/api/v2/evolution-chain/10/".to_string()),
+                is_legendary: false,
+                is_mythical: false,
+            }
+        );
+
+        // Newer species have no habitat, and entries may lack an English text
+        species["habitat"] = Value::Null;
+        species["flavor_text_entries"].as_array_mut().unwrap().pop();
+        let parsed = PokemonSpecies::from_json(&species).unwrap();
+        assert_eq!((parsed.habitat, parsed.flavor_text), (None, None));
+
+        species.as_object_mut().unwrap().remove("color");
+        assert_eq!(PokemonSpecies::from_json(&species), None);
+    }
+
+    #[test]
+    fn test_type_members() {
+        let document = serde_json::json!({
+            "id": 13,
+            "name": "electric",
+            "pokemon": [
+                { "pokemon": { "name": "pikachu", "url": "https://pokeapi.co/api/v2/pokemon/25/" }, "slot": 1 },
+                { "pokemon": { "name": "magnemite", "url": "https://pokeapi.co/api/v2/pokemon/81" }, "slot": 1 },
+                { "pokemon": { "name": "mystery", "url": "https://pokeapi.co/api/v2/pokemon/" }, "slot": 2 }
+            ]
+        });
+        let member = |name: &str, id| TypeMember {
+            name: name.to_string(),
+            id,
+        };
+        assert_eq!(
+            type_members(&document).unwrap(),
+            [member("pikachu", Some(25)), member("magnemite", Some(81)), member("mystery", None)]
+        );
+        assert_eq!(type_members(&serde_json::json!({ "name": "electric" })), None);
+    }
+
+    #[test]
+    fn test_is_pokemon_path() {
+        assert!(is_pokemon_path("/pokemon/25"));
+        assert!(is_pokemon_path("/pokemon/pikachu"));
+        assert!(!is_pokemon_path("/pokemon/25/encounters"));
+        assert!(!is_pokemon_path("/pokemon-species/25"));
+        assert!(!is_pokemon_path("/pokemon/"));
+    }
+
+    #[test]
+    fn test_truncates_moves_and_sets_indicator() {
+        let body = r#"{"id": 25, "moves": [1, 2, 3, 4], "forms": [1]}"#;
+        let shaped: Value = serde_json::from_str(&shape_pokemon_body(&config(Some(2), Some(5)), body)).unwrap();
+
+        assert_eq!(shaped["moves"].as_array().unwrap().len(), 2);
+        assert_eq!(shaped["moves_truncated"], Value::Bool(true));
+        assert_eq!(shaped["forms"].as_array().unwrap().len(), 1);
+        assert_eq!(shaped["forms_truncated"], Value::Bool(false));
+    }
+
+    #[test]
+    fn test_no_caps_leaves_body_untouched() {
+        let body = r#"{"id": 25, "moves": [1, 2, 3, 4]}"#;
+        assert!(matches!(shape_pokemon_body(&config(None, None), body), Cow::Borrowed(shaped) if shaped == body));
+    }
+
+    fn summary_json(id: u64, name: &str, hp: i64, attack: i64) -> Value {
+        serde_json::json!({
+            "id": id,
+            "name": name,
+            "height": 4,
+            "weight": 60,
+            "types": [{"slot": 1, "type": {"name": "electric", "url": ""}}],
+            "stats": [
+                {"base_stat": hp, "effort": 0, "stat": {"name": "hp", "url": ""}},
+                {"base_stat": attack, "effort": 0, "stat": {"name": "attack", "url": ""}}
+            ]
+        })
+    }
+
+    #[test]
+    fn test_strip_moves() {
+        let body = r#"{"id":25,"moves":[{"move":{"name":"thunderbolt"}}],"moves_truncated":false}"#;
+        assert_eq!(strip_moves(body), r#"{"id":25}"#);
+
+        // Documents without moves pass through untouched, without a copy
+        assert!(matches!(strip_moves(r#"{"id": 25}"#), Cow::Borrowed(r#"{"id": 25}"#)));
+    }
+
+    #[test]
+    fn test_compare_stat_deltas() {
+        let pikachu = PokemonSummary::from_json(&summary_json(25, "pikachu", 35, 55)).unwrap();
+        let charizard = PokemonSummary::from_json(&summary_json(6, "charizard", 78, 84)).unwrap();
+
+        let comparison = compare_pokemon(vec![pikachu, charizard]);
+
+        assert_eq!(comparison.pokemon[0].types, vec!["electric"]);
+        assert_eq!(comparison.stat_deltas["hp"], vec![0, 43]);
+        assert_eq!(comparison.stat_deltas["attack"], vec![0, 29]);
+    }
+
+    #[test]
+    fn test_parse_compare_ids() {
+        assert_eq!(parse_compare_ids("25,6", true), Ok(vec![25, 6]));
+        assert!(parse_compare_ids("25", true).is_err());
+        assert!(parse_compare_ids("1,2,3,4,5,6,7", true).is_err());
+        assert!(parse_compare_ids("25,0", true).is_err());
+        assert!(parse_compare_ids("25,9999", true).is_err());
+        assert!(parse_compare_ids("25,abc", true).is_err());
+    }
+
+    #[cfg(feature = "random")]
+    #[test]
+    fn test_sample_distinct_ids() {
+        let ids = sample_distinct_ids(50);
+        assert_eq!(ids.len(), 50);
+        assert_eq!(ids.iter().collect::<HashSet<_>>().len(), 50);
+        assert!(ids.iter().all(|id| (1..=MAX_POKEMON_ID).contains(id)));
+
+        // Never more than the dex holds
+        assert_eq!(sample_distinct_ids(5000).len(), MAX_POKEMON_ID as usize);
+    }
+
+    #[cfg(feature = "random")]
+    #[test]
+    fn test_random_pool() {
+        let mut config = config(None, None);
+        assert_eq!(random_pool(&config, None).unwrap().len(), MAX_POKEMON_ID as usize);
+
+        config.random_min = 10;
+        config.random_max = 14;
+        config.random_exclude = vec![11, 13, 99];
+        assert_eq!(random_pool(&config, None), Ok(vec![10, 12, 14]));
+
+        let allowed = AllowedIds::from_specs(&[IdSpec::Range("12-20".to_string())]).unwrap();
+        assert_eq!(random_pool(&config, Some(&allowed)), Ok(vec![12, 14]));
+
+        config.random_exclude = vec![12, 13, 14];
+        assert!(random_pool(&config, Some(&allowed)).is_err());
+
+        config.random_min = 15;
+        assert_eq!(
+            random_pool(&config, None).unwrap_err(),
+            "pokemon.random_min 15 is above random_max 14"
+        );
+    }
+
+    #[test]
+    fn test_duplicate_ids_deduped() {
+        assert_eq!(parse_compare_ids("6,25,6,1", true), Ok(vec![6, 25, 1]));
+        // Deduping can leave too few ids to compare
+        assert!(parse_compare_ids("25,25", true).is_err());
+    }
+
+    #[test]
+    fn test_duplicate_ids_preserved() {
+        assert_eq!(parse_compare_ids("6,25,6", false), Ok(vec![6, 25, 6]));
+        assert_eq!(parse_compare_ids("25,25", false), Ok(vec![25, 25]));
+    }
+
+    #[test]
+    fn test_sort_pokemon_bodies() {
+        let mut bodies = vec![
+            r#"{"id": 25, "name": "pikachu"}"#.to_string(),
+            r#"{"id": 6, "name": "charizard"}"#.to_string(),
+            r#"{"name": "missingno"}"#.to_string(),
+            r#"{"id": 150, "name": "mewtwo"}"#.to_string(),
+        ];
+
+        sort_pokemon_bodies(&mut bodies, PokemonSort::Id);
+        assert!(bodies[0].contains("charizard"));
+        assert!(bodies[1].contains("pikachu"));
+        assert!(bodies[2].contains("mewtwo"));
+        assert!(bodies[3].contains("missingno"));
+
+        sort_pokemon_bodies(&mut bodies, PokemonSort::Name);
+        let names: Vec<_> = bodies
+            .iter()
+            .map(|body| serde_json::from_str::<Value>(body).unwrap()["name"].clone())
+            .collect();
+        assert_eq!(names, ["charizard", "mewtwo", "missingno", "pikachu"]);
+    }
+
+    #[test]
+    fn test_allowed_ids_from_specs() {
+        let allowed = AllowedIds::from_specs(&[
+            IdSpec::Range("1-3".to_string()),
+            IdSpec::Id(25),
+            IdSpec::Id(2),
+        ])
+        .unwrap();
+        assert_eq!(allowed.ids(), [1, 2, 3, 25]);
+        assert!(allowed.contains(25));
+        assert!(!allowed.contains(4));
+
+        assert!(AllowedIds::from_specs(&[]).is_err());
+        assert!(AllowedIds::from_specs(&[IdSpec::Id(0)]).is_err());
+        assert!(AllowedIds::from_specs(&[IdSpec::Range("1-2000".to_string())]).is_err());
+        assert!(AllowedIds::from_specs(&[IdSpec::Range("151-1".to_string())]).is_err());
+        assert!(AllowedIds::from_specs(&[IdSpec::Range("kanto".to_string())]).is_err());
+    }
+
+    #[test]
+    fn test_id_mapping() {
+        let config = IdMappingConfig {
+            offset: 10_000,
+            map: BTreeMap::from([("25".to_string(), 7)]),
+        };
+        let mapping = IdMapping::from_config(&config).unwrap();
+
+        assert_eq!(mapping.upstream_id(25), Some(7));
+        assert_eq!(mapping.upstream_id(1), Some(10_001));
+        assert_eq!(mapping.client_id(7), Some(25));
+        assert_eq!(mapping.client_id(10_001), Some(1));
+        assert_eq!(mapping.upstream_path("/pokemon/25"), "/pokemon/7");
+        assert_eq!(mapping.upstream_path("/pokemon/pikachu"), "/pokemon/pikachu");
+        assert_eq!(mapping.to_client_body(r#"{"id":7}"#.to_string()), r#"{"id":25}"#);
+
+        // Negative offsets that leave the id range and colliding entries are rejected
+        let config = IdMappingConfig { offset: -1, ..Default::default() };
+        assert!(IdMapping::from_config(&config).is_err());
+        let config = IdMappingConfig {
+            offset: 0,
+            map: BTreeMap::from([("25".to_string(), 6)]),
+        };
+        assert!(IdMapping::from_config(&config).is_err());
+        let config = IdMappingConfig {
+            map: BTreeMap::from([("pikachu".to_string(), 6)]),
+            ..Default::default()
+        };
+        assert!(IdMapping::from_config(&config).is_err());
+    }
+}
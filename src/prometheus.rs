@@ -0,0 +1,68 @@
+use axum::{
+    body::Body,
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+pub const HTTP_REQUESTS_TOTAL: &str = "http_requests_total";
+pub const UPSTREAM_FETCH_SECONDS: &str = "upstream_fetch_duration_seconds";
+pub const UPSTREAM_ERRORS_TOTAL: &str = "upstream_errors_total";
+pub const CACHE_SIZE: &str = "cache_size";
+pub const CACHE_HIT_RATE: &str = "cache_hit_rate";
+
+// How often recorded histogram samples are folded into the exported buckets
+pub const UPKEEP_INTERVAL: Duration = Duration::from_secs(5);
+
+// Upstream latency buckets in seconds, up to the longest sensible timeout
+const UPSTREAM_FETCH_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+
+// The process-wide Prometheus recorder, installed on first use. Metrics
+// recorded before this is called are dropped.
+pub fn handle() -> &'static PrometheusHandle {
+    HANDLE.get_or_init(|| {
+        let recorder = PrometheusBuilder::new()
+            .set_buckets_for_metric(Matcher::Full(UPSTREAM_FETCH_SECONDS.to_string()), UPSTREAM_FETCH_BUCKETS)
+            .expect("upstream fetch buckets are not empty")
+            .build_recorder();
+        let handle = recorder.handle();
+        if let Err(e) = metrics::set_global_recorder(recorder) {
+            tracing::warn!("Another metrics recorder is installed, /metrics will stay empty: {}", e);
+        }
+        handle
+    })
+}
+
+// Middleware counting requests by method, matched route and status. Routes
+// are the templates (`/pokemon/{id}`), so label cardinality stays bounded.
+pub async fn track_requests(request: Request<Body>, next: Next) -> Response {
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or_else(|| "unmatched".to_string(), |path| path.as_str().to_string());
+
+    let response = next.run(request).await;
+    let status = response.status().as_u16().to_string();
+    metrics::counter!(HTTP_REQUESTS_TOTAL, "method" => method, "route" => route, "status" => status).increment(1);
+    response
+}
+
+// One upstream fetch: its latency, and whether it failed
+pub fn record_upstream_fetch(elapsed: Duration, failed: bool) {
+    metrics::histogram!(UPSTREAM_FETCH_SECONDS).record(elapsed.as_secs_f64());
+    if failed {
+        metrics::counter!(UPSTREAM_ERRORS_TOTAL).increment(1);
+    }
+}
+
+// Cache gauges, refreshed on each scrape
+pub fn record_cache(size: usize, hit_rate: f64) {
+    metrics::gauge!(CACHE_SIZE).set(size as f64);
+    metrics::gauge!(CACHE_HIT_RATE).set(hit_rate);
+}
@@ -0,0 +1,211 @@
+use crate::error::error_response;
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// Past this many tracked clients, buckets that have refilled are dropped; a
+// full bucket behaves exactly like a missing one
+const PRUNE_THRESHOLD: usize = 10_000;
+
+// Token bucket per client IP: each holds up to `burst` tokens, refilled at
+// `per_second`, and every request spends one
+#[derive(Debug)]
+pub struct RateLimiter {
+    per_second: f64,
+    burst: f64,
+    buckets: Mutex<HashMap<IpAddr, Bucket>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(per_second: f64, burst: u32) -> Self {
+        Self {
+            per_second,
+            burst: burst as f64,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Spend a token for `ip`, or return how long until one is available
+    pub fn try_acquire(&self, ip: IpAddr) -> Result<(), Duration> {
+        self.try_acquire_at(ip, Instant::now())
+    }
+
+    fn try_acquire_at(&self, ip: IpAddr, now: Instant) -> Result<(), Duration> {
+        // A poisoned map shouldn't take the proxy down with it
+        let Ok(mut buckets) = self.buckets.lock() else {
+            return Ok(());
+        };
+        if buckets.len() >= PRUNE_THRESHOLD {
+            buckets.retain(|_, bucket| self.refilled(*bucket, now) < self.burst);
+        }
+
+        let bucket = buckets.entry(ip).or_insert(Bucket {
+            tokens: self.burst,
+            updated: now,
+        });
+        bucket.tokens = self.refilled(*bucket, now);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / self.per_second))
+        }
+    }
+
+    fn refilled(&self, bucket: Bucket, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        (bucket.tokens + elapsed * self.per_second).min(self.burst)
+    }
+}
+
+// Limiter plus where to read the client IP from
+#[derive(Debug)]
+pub struct RateLimitState {
+    pub limiter: RateLimiter,
+    // Use the last `X-Forwarded-For` address instead of the peer address.
+    // Only safe behind exactly one proxy that appends to the header.
+    pub trust_forwarded_for: bool,
+}
+
+// The address a request is limited by: the last `X-Forwarded-For` address
+// when that is trusted and present, else the peer address
+pub(crate) fn client_ip(request: &Request<Body>, trust_forwarded_for: bool) -> Option<IpAddr> {
+    let forwarded = if trust_forwarded_for {
+        forwarded_for(request.headers())
+    } else {
+        None
+    };
+    forwarded.or_else(|| {
+        request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip())
+    })
+}
+
+// The right-most address is the one our proxy appended; anything before it
+// came from the client and can be forged
+fn forwarded_for(headers: &HeaderMap) -> Option<IpAddr> {
+    headers
+        .get("x-forwarded-for")?
+        .to_str()
+        .ok()?
+        .rsplit(',')
+        .next()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+// Middleware rejecting requests with 429 and `Retry-After` once the client's
+// bucket is empty
+pub async fn limit_rate(
+    State(state): State<Arc<RateLimitState>>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let Some(ip) = client_ip(&request, state.trust_forwarded_for) else {
+        return next.run(request).await;
+    };
+
+    match state.limiter.try_acquire(ip) {
+        Ok(()) => next.run(request).await,
+        Err(wait) => {
+            tracing::warn!("Rejecting request from {}: rate limit exceeded", ip);
+            // Whole seconds, rounded up so a client that honors it gets a token
+            let retry_after = wait.as_secs_f64().ceil().max(1.0) as u64;
+            let mut response = error_response(StatusCode::TOO_MANY_REQUESTS, "Rate limit exceeded");
+            response.headers_mut().insert(header::RETRY_AFTER, retry_after.into());
+            response
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{middleware, routing::get, Router};
+    use tower::ServiceExt;
+
+    fn request_from(ip: [u8; 4]) -> Request<Body> {
+        let mut request = Request::get("/").body(Body::empty()).unwrap();
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from((ip, 4000))));
+        request
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_per_ip() {
+        let state = Arc::new(RateLimitState {
+            limiter: RateLimiter::new(0.5, 3),
+            trust_forwarded_for: false,
+        });
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(middleware::from_fn_with_state(state, limit_rate));
+
+        // The burst is served, then the bucket is empty
+        for _ in 0..3 {
+            let response = app.clone().oneshot(request_from([10, 0, 0, 1])).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        let rejected = app.clone().oneshot(request_from([10, 0, 0, 1])).await.unwrap();
+        assert_eq!(rejected.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(rejected.headers()[header::RETRY_AFTER], "2");
+        let body = axum::body::to_bytes(rejected.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], br#"{"error":"Rate limit exceeded","code":429}"#);
+
+        // Another client has its own bucket
+        let other = app.clone().oneshot(request_from([10, 0, 0, 2])).await.unwrap();
+        assert_eq!(other.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn test_forwarded_for_uses_last_address() {
+        // The client wrote the first entry; our proxy appended the second
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", "198.51.100.1, 203.0.113.7".parse().unwrap());
+        assert_eq!(forwarded_for(&headers), Some(IpAddr::from([203, 0, 113, 7])));
+
+        headers.insert("x-forwarded-for", "203.0.113.7".parse().unwrap());
+        assert_eq!(forwarded_for(&headers), Some(IpAddr::from([203, 0, 113, 7])));
+
+        headers.insert("x-forwarded-for", "unknown".parse().unwrap());
+        assert_eq!(forwarded_for(&headers), None);
+    }
+
+    #[test]
+    fn test_bucket_refills_over_time() {
+        let limiter = RateLimiter::new(2.0, 1);
+        let ip = IpAddr::from([10, 0, 0, 1]);
+        let start = Instant::now();
+
+        assert!(limiter.try_acquire_at(ip, start).is_ok());
+        assert_eq!(
+            limiter.try_acquire_at(ip, start),
+            Err(Duration::from_millis(500))
+        );
+        assert!(limiter.try_acquire_at(ip, start + Duration::from_millis(500)).is_ok());
+        // Idle time beyond a full bucket doesn't bank extra tokens
+        let later = start + Duration::from_secs(60);
+        assert!(limiter.try_acquire_at(ip, later).is_ok());
+        assert!(limiter.try_acquire_at(ip, later).is_err());
+    }
+}
@@ -0,0 +1,729 @@
+use crate::async_cache::{unblock, AsyncCacheTrait};
+use crate::cache::{ttl_for_key, CacheError, CacheStats, CacheTrait, EntryAge};
+use crate::config::CacheConfig;
+use futures::future::BoxFuture;
+use redis::Commands;
+use serde::{de::DeserializeOwned, Serialize};
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// Every entry is written under this prefix, so `clear` leaves other data in a
+// shared Redis alone
+const KEY_PREFIX: &str = "pokemon-api-proxy:";
+// Sorted set of our keys scored by expiry time (Unix ms), so `size` and
+// `keys` don't scan the keyspace. Outside `KEY_PREFIX`, so no entry can
+// collide with it.
+const INDEX_KEY: &str = "pokemon-api-proxy-index";
+// Fields of an entry's hash: the JSON value and the TTL it was written with
+const VALUE_FIELD: &str = "value";
+const TTL_FIELD: &str = "ttl_ms";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Cache backed by Redis, shared by every instance pointed at the same server
+// and surviving restarts. Each entry is a hash of its JSON value and original
+// TTL, with that TTL set on the key, so Redis expires them itself. `max_size`
+// is not enforced here: bound memory with Redis's `maxmemory` and an eviction
+// policy instead. Redis keeps no per-key hit counts, so `access_counts` is
+// always empty. Through `AsyncCacheTrait` each command runs on the blocking pool.
+pub struct RedisCache<T> {
+    pool: r2d2::Pool<redis::Client>,
+    config: CacheConfig,
+    stats: Mutex<CacheStats>,
+    _value: PhantomData<fn() -> T>,
+}
+
+impl<T> RedisCache<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    // Connect to `config.redis_url`; fails when it is unset, malformed or unreachable
+    pub fn new(config: CacheConfig) -> Result<Self, CacheError> {
+        let url = config
+            .redis_url
+            .as_ref()
+            .ok_or_else(|| CacheError::Backend("cache.redis_url is required for the redis cache".to_string()))?;
+        let client = redis::Client::open(url.expose()).map_err(backend_error)?;
+        let pool = r2d2::Pool::builder()
+            .connection_timeout(CONNECT_TIMEOUT)
+            .build(client)
+            .map_err(backend_error)?;
+
+        tracing::info!("Initializing Redis cache with expiration: {}s", config.expiration);
+        Ok(Self {
+            pool,
+            config,
+            stats: Mutex::new(CacheStats::default()),
+            _value: PhantomData,
+        })
+    }
+
+    // Run `command` on a pooled connection, blocking the calling thread
+    fn run<R>(&self, command: impl FnOnce(&mut redis::Connection) -> redis::RedisResult<R>) -> Result<R, CacheError> {
+        run_on(&self.pool, command)
+    }
+
+    // Same as `run`, on the blocking pool
+    async fn run_unblocked<R>(
+        &self,
+        command: impl FnOnce(&mut redis::Connection) -> redis::RedisResult<R> + Send + 'static,
+    ) -> Result<R, CacheError>
+    where
+        R: Send + 'static,
+    {
+        let pool = self.pool.clone();
+        unblock(move || run_on(&pool, command)).await?
+    }
+
+    fn read(&self, key: &str) -> Result<Option<Arc<T>>, CacheError> {
+        let bytes = self.run(|connection| read_value(connection, key))?;
+        bytes.map(|bytes| deserialize(&bytes)).transpose()
+    }
+
+    // Count a read's outcome and decode it
+    fn finish_get(&self, key: &str, read: Result<Option<Vec<u8>>, CacheError>) -> Option<Arc<T>> {
+        match read.and_then(|bytes| bytes.map(|bytes| deserialize(&bytes)).transpose()) {
+            Ok(Some(value)) => {
+                tracing::debug!("Cache hit for key: {}", key);
+                self.record(|stats| stats.hits += 1);
+                Some(value)
+            }
+            Ok(None) => {
+                tracing::debug!("Cache miss for key: {}", key);
+                self.record(|stats| stats.misses += 1);
+                None
+            }
+            Err(e) => {
+                tracing::error!("Failed to read cache entry for key {}: {}", key, e);
+                self.record(|stats| stats.misses += 1);
+                None
+            }
+        }
+    }
+
+    // The bytes and TTL in milliseconds to write, or `None` when there is
+    // nothing to store. The insert is counted by the caller once it succeeds.
+    fn prepare_insert(&self, key: &str, value: &T, ttl: Duration) -> Result<Option<(Vec<u8>, u64)>, CacheError> {
+        if key.is_empty() {
+            return Err(CacheError::InvalidKey("Key cannot be empty".to_string()));
+        }
+
+        // A zero TTL expires the entry at once, so there is nothing to store
+        if ttl.is_zero() {
+            return Ok(None);
+        }
+
+        let bytes = serde_json::to_vec(value).map_err(|e| CacheError::Serialization(e.to_string()))?;
+        Ok(Some((bytes, ttl.as_millis() as u64)))
+    }
+
+    fn finish_remove(&self, key: &str, removed: Result<Option<Vec<u8>>, CacheError>) -> Option<Arc<T>> {
+        match removed {
+            Ok(Some(bytes)) => {
+                tracing::debug!("Removed cache entry: {}", key);
+                self.record(|stats| stats.removes += 1);
+                deserialize(&bytes).ok()
+            }
+            Ok(None) => None,
+            Err(e) => {
+                tracing::error!("Failed to remove cache entry for key {}: {}", key, e);
+                None
+            }
+        }
+    }
+
+    fn finish_clear(&self, cleared: Result<usize, CacheError>) {
+        match cleared {
+            Ok(size) => {
+                tracing::info!("Cleared cache ({} entries)", size);
+                self.record(|stats| *stats = CacheStats::default());
+            }
+            Err(e) => tracing::error!("Failed to clear Redis cache: {}", e),
+        }
+    }
+
+    fn record(&self, update: impl FnOnce(&mut CacheStats)) {
+        if let Ok(mut stats) = self.stats.lock() {
+            update(&mut stats);
+        }
+    }
+}
+
+fn run_on<R>(
+    pool: &r2d2::Pool<redis::Client>,
+    command: impl FnOnce(&mut redis::Connection) -> redis::RedisResult<R>,
+) -> Result<R, CacheError> {
+    let mut connection = pool.get().map_err(backend_error)?;
+    command(&mut connection).map_err(backend_error)
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since_epoch| since_epoch.as_millis() as u64)
+}
+
+fn read_value(connection: &mut redis::Connection, key: &str) -> redis::RedisResult<Option<Vec<u8>>> {
+    connection.hget(namespaced(key), VALUE_FIELD)
+}
+
+// Store the entry and index it, in one transaction so the two never disagree
+fn write_entry(connection: &mut redis::Connection, key: &str, bytes: Vec<u8>, ttl_ms: u64) -> redis::RedisResult<()> {
+    let entry = namespaced(key);
+    redis::pipe()
+        .atomic()
+        .del(&entry)
+        .ignore()
+        .cmd("HSET")
+        .arg(&entry)
+        .arg(VALUE_FIELD)
+        .arg(bytes)
+        .arg(TTL_FIELD)
+        .arg(ttl_ms)
+        .ignore()
+        .pexpire(&entry, ttl_ms as i64)
+        .ignore()
+        .zadd(INDEX_KEY, key, now_ms().saturating_add(ttl_ms))
+        .ignore()
+        .query(connection)
+}
+
+fn take_entry(connection: &mut redis::Connection, key: &str) -> redis::RedisResult<Option<Vec<u8>>> {
+    let entry = namespaced(key);
+    let (bytes,): (Option<Vec<u8>>,) = redis::pipe()
+        .atomic()
+        .hget(&entry, VALUE_FIELD)
+        .del(&entry)
+        .ignore()
+        .zrem(INDEX_KEY, key)
+        .ignore()
+        .query(connection)?;
+    Ok(bytes)
+}
+
+// Drop index members whose entries Redis has expired by now
+fn prune_index(pipe: &mut redis::Pipeline) -> &mut redis::Pipeline {
+    pipe.zrembyscore(INDEX_KEY, "-inf", now_ms()).ignore()
+}
+
+// Our live keys, without the namespace
+fn indexed_keys(connection: &mut redis::Connection) -> redis::RedisResult<Vec<String>> {
+    let (keys,): (Vec<String>,) = prune_index(&mut redis::pipe())
+        .zrange(INDEX_KEY, 0, -1)
+        .query(connection)?;
+    Ok(keys)
+}
+
+fn indexed_count(connection: &mut redis::Connection) -> redis::RedisResult<usize> {
+    let (count,): (usize,) = prune_index(&mut redis::pipe()).zcard(INDEX_KEY).query(connection)?;
+    Ok(count)
+}
+
+// Delete every entry of ours and the index, returning how many there were
+fn delete_all(connection: &mut redis::Connection) -> redis::RedisResult<usize> {
+    let keys = indexed_keys(connection)?;
+    let mut pipe = redis::pipe();
+    pipe.atomic();
+    for key in &keys {
+        pipe.del(namespaced(key)).ignore();
+    }
+    pipe.del(INDEX_KEY).ignore().query::<()>(connection)?;
+    Ok(keys.len())
+}
+
+// How long ago the entry was written and how long it has left, from the TTL
+// stored with it and the key's remaining PTTL
+fn read_entry_age(connection: &mut redis::Connection, key: &str) -> redis::RedisResult<Option<EntryAge>> {
+    let entry = namespaced(key);
+    // PTTL is negative for a missing key or one without an expiry
+    let (ttl_ms, remaining_ms): (Option<u64>, i64) =
+        redis::pipe().hget(&entry, TTL_FIELD).pttl(&entry).query(connection)?;
+    let (Some(ttl_ms), Ok(remaining_ms)) = (ttl_ms, u64::try_from(remaining_ms)) else {
+        return Ok(None);
+    };
+    let ttl = Duration::from_millis(ttl_ms);
+    let ttl_remaining = Duration::from_millis(remaining_ms);
+    Ok(Some(EntryAge {
+        age: ttl.saturating_sub(ttl_remaining),
+        ttl_remaining,
+    }))
+}
+
+fn finish_size(count: Result<usize, CacheError>) -> usize {
+    count.unwrap_or_else(|e| {
+        tracing::error!("Failed to count Redis cache entries: {}", e);
+        0
+    })
+}
+
+fn finish_keys(keys: Result<Vec<String>, CacheError>) -> Vec<String> {
+    keys.unwrap_or_else(|e| {
+        tracing::error!("Failed to list Redis cache keys: {}", e);
+        Vec::new()
+    })
+}
+
+fn namespaced(key: &str) -> String {
+    format!("{}{}", KEY_PREFIX, key)
+}
+
+fn deserialize<T: DeserializeOwned>(bytes: &[u8]) -> Result<Arc<T>, CacheError> {
+    serde_json::from_slice(bytes)
+        .map(Arc::new)
+        .map_err(|e| CacheError::Serialization(e.to_string()))
+}
+
+fn backend_error(err: impl std::fmt::Display) -> CacheError {
+    CacheError::Backend(err.to_string())
+}
+
+impl<T> CacheTrait<T> for RedisCache<T>
+where
+    T: Clone + Send + Sync + Serialize + DeserializeOwned,
+{
+    fn get(&self, key: &str) -> Option<Arc<T>> {
+        if key.is_empty() {
+            tracing::warn!("Attempted to get cache entry with empty key");
+            return None;
+        }
+
+        let read = self.run(|connection| read_value(connection, key));
+        self.finish_get(key, read)
+    }
+
+    fn insert(&self, key: String, value: T) -> Result<(), CacheError> {
+        let ttl = ttl_for_key(&self.config, &key);
+        self.insert_with_ttl(key, value, ttl)
+    }
+
+    fn insert_with_ttl(&self, key: String, value: T, ttl: Duration) -> Result<(), CacheError> {
+        if let Some((bytes, ttl_ms)) = self.prepare_insert(&key, &value, ttl)? {
+            self.run(|connection| write_entry(connection, &key, bytes, ttl_ms))?;
+            tracing::debug!("Inserted into Redis cache: {}", key);
+        }
+        self.record(|stats| stats.inserts += 1);
+        Ok(())
+    }
+
+    fn remove(&self, key: &str) -> Option<Arc<T>> {
+        if key.is_empty() {
+            tracing::warn!("Attempted to remove cache entry with empty key");
+            return None;
+        }
+
+        let removed = self.run(|connection| take_entry(connection, key));
+        self.finish_remove(key, removed)
+    }
+
+    fn clear(&self) {
+        self.finish_clear(self.run(delete_all));
+    }
+
+    fn size(&self) -> usize {
+        finish_size(self.run(indexed_count))
+    }
+
+    fn hit_rate(&self) -> f64 {
+        self.stats.lock().map_or(0.0, |stats| stats.hit_rate())
+    }
+
+    // Redis expires the entries itself; only the index needs pruning
+    fn cleanup_expired(&self) {
+        if let Err(e) = self.run(|connection| prune_index(&mut redis::pipe()).query::<()>(connection)) {
+            tracing::error!("Failed to prune Redis cache index: {}", e);
+        }
+    }
+
+    fn stats(&self) -> Option<CacheStats> {
+        self.stats.lock().ok().map(|stats| stats.clone())
+    }
+
+    fn keys(&self) -> Vec<String> {
+        finish_keys(self.run(indexed_keys))
+    }
+
+    fn peek(&self, key: &str) -> Option<Arc<T>> {
+        self.read(key).ok().flatten()
+    }
+
+    fn entry_age(&self, key: &str) -> Option<EntryAge> {
+        self.run(|connection| read_entry_age(connection, key)).ok().flatten()
+    }
+
+    fn access_counts(&self) -> Vec<(String, u64)> {
+        Vec::new()
+    }
+}
+
+impl<T> AsyncCacheTrait<T> for RedisCache<T>
+where
+    T: Clone + Send + Sync + Serialize + DeserializeOwned + 'static,
+{
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<Arc<T>>> {
+        Box::pin(async move {
+            if key.is_empty() {
+                tracing::warn!("Attempted to get cache entry with empty key");
+                return None;
+            }
+
+            let owned = key.to_string();
+            let read = self.run_unblocked(move |connection| read_value(connection, &owned)).await;
+            self.finish_get(key, read)
+        })
+    }
+
+    fn insert(&self, key: String, value: T) -> BoxFuture<'_, Result<(), CacheError>> {
+        Box::pin(async move {
+            let ttl = ttl_for_key(&self.config, &key);
+            if let Some((bytes, ttl_ms)) = self.prepare_insert(&key, &value, ttl)? {
+                let owned = key.clone();
+                self.run_unblocked(move |connection| write_entry(connection, &owned, bytes, ttl_ms))
+                    .await?;
+                tracing::debug!("Inserted into Redis cache: {}", key);
+            }
+            self.record(|stats| stats.inserts += 1);
+            Ok(())
+        })
+    }
+
+    fn remove<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<Arc<T>>> {
+        Box::pin(async move {
+            if key.is_empty() {
+                tracing::warn!("Attempted to remove cache entry with empty key");
+                return None;
+            }
+
+            let owned = key.to_string();
+            let removed = self.run_unblocked(move |connection| take_entry(connection, &owned)).await;
+            self.finish_remove(key, removed)
+        })
+    }
+
+    fn clear(&self) -> BoxFuture<'_, ()> {
+        Box::pin(async move { self.finish_clear(self.run_unblocked(delete_all).await) })
+    }
+
+    fn size(&self) -> BoxFuture<'_, usize> {
+        Box::pin(async move { finish_size(self.run_unblocked(indexed_count).await) })
+    }
+
+    fn keys(&self) -> BoxFuture<'_, Vec<String>> {
+        Box::pin(async move { finish_keys(self.run_unblocked(indexed_keys).await) })
+    }
+
+    fn peek<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<Arc<T>>> {
+        Box::pin(async move {
+            let owned = key.to_string();
+            let bytes = self.run_unblocked(move |connection| read_value(connection, &owned)).await;
+            bytes.ok().flatten().and_then(|bytes| deserialize(&bytes).ok())
+        })
+    }
+
+    fn entry_age<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<EntryAge>> {
+        Box::pin(async move {
+            let owned = key.to_string();
+            self.run_unblocked(move |connection| read_entry_age(connection, &owned))
+                .await
+                .ok()
+                .flatten()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Not the whole parent: with both cache traits in scope `insert` and
+    // friends would be ambiguous
+    use super::{CacheConfig, CacheError, CacheTrait, RedisCache};
+    use crate::async_cache;
+    use crate::config::Secret;
+    use std::collections::HashMap;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_new_rejects_missing_or_malformed_url() {
+        let missing = RedisCache::<String>::new(CacheConfig::default());
+        assert!(matches!(missing, Err(CacheError::Backend(_))));
+
+        let malformed = RedisCache::<String>::new(CacheConfig {
+            redis_url: Some(Secret::new("not a url")),
+            ..Default::default()
+        });
+        assert!(matches!(malformed, Err(CacheError::Backend(_))));
+    }
+
+    enum Reply {
+        Status(&'static str),
+        Int(i64),
+        Bulk(Option<Vec<u8>>),
+        Array(Vec<Reply>),
+        Error(String),
+    }
+
+    impl Reply {
+        fn write_to(&self, out: &mut Vec<u8>) {
+            match self {
+                Reply::Status(status) => out.extend_from_slice(format!("+{}\r\n", status).as_bytes()),
+                Reply::Int(n) => out.extend_from_slice(format!(":{}\r\n", n).as_bytes()),
+                Reply::Bulk(None) => out.extend_from_slice(b"$-1\r\n"),
+                Reply::Bulk(Some(bytes)) => {
+                    out.extend_from_slice(format!("${}\r\n", bytes.len()).as_bytes());
+                    out.extend_from_slice(bytes);
+                    out.extend_from_slice(b"\r\n");
+                }
+                Reply::Array(items) => {
+                    out.extend_from_slice(format!("*{}\r\n", items.len()).as_bytes());
+                    items.iter().for_each(|item| item.write_to(out));
+                }
+                Reply::Error(message) => out.extend_from_slice(format!("-ERR {}\r\n", message).as_bytes()),
+            }
+        }
+    }
+
+    // A hash's fields and expiry
+    type MockHash = (HashMap<String, Vec<u8>>, Option<Instant>);
+
+    // Just enough of Redis for this backend: hashes with expiry, one sorted
+    // set, and MULTI/EXEC. Keeps real time, as Redis does.
+    #[derive(Default)]
+    struct MockRedis {
+        hashes: HashMap<String, MockHash>,
+        zsets: HashMap<String, Vec<(f64, String)>>,
+        // Abort every transaction, as a server out of memory would
+        fail_transactions: bool,
+    }
+
+    impl MockRedis {
+        fn live_hash(&mut self, key: &str) -> Option<&mut MockHash> {
+            if self.hashes.get(key).is_some_and(|(_, expires)| expires.is_some_and(|at| at <= Instant::now())) {
+                self.hashes.remove(key);
+            }
+            self.hashes.get_mut(key)
+        }
+
+        fn execute(&mut self, args: &[String]) -> Reply {
+            let arg = |i: usize| args[i].clone();
+            match args[0].to_ascii_uppercase().as_str() {
+                "PING" => Reply::Status("PONG"),
+                "CLIENT" => Reply::Status("OK"),
+                "HSET" => {
+                    let fields = &mut self.hashes.entry(arg(1)).or_default().0;
+                    for pair in args[2..].chunks(2) {
+                        fields.insert(pair[0].clone(), pair[1].clone().into_bytes());
+                    }
+                    Reply::Int((args.len() as i64 - 2) / 2)
+                }
+                "HGET" => Reply::Bulk(self.live_hash(&arg(1)).and_then(|(fields, _)| fields.get(&arg(2)).cloned())),
+                "PEXPIRE" => match self.live_hash(&arg(1)) {
+                    Some((_, expires)) => {
+                        *expires = Some(Instant::now() + Duration::from_millis(arg(2).parse().unwrap()));
+                        Reply::Int(1)
+                    }
+                    None => Reply::Int(0),
+                },
+                "PTTL" => Reply::Int(match self.live_hash(&arg(1)) {
+                    Some((_, Some(at))) => at.saturating_duration_since(Instant::now()).as_millis() as i64,
+                    Some((_, None)) => -1,
+                    None => -2,
+                }),
+                "DEL" => Reply::Int(
+                    args[1..]
+                        .iter()
+                        .filter(|key| self.hashes.remove(*key).is_some() | self.zsets.remove(*key).is_some())
+                        .count() as i64,
+                ),
+                "ZADD" => {
+                    let set = self.zsets.entry(arg(1)).or_default();
+                    set.retain(|(_, member)| *member != args[3]);
+                    set.push((arg(2).parse().unwrap(), arg(3)));
+                    Reply::Int(1)
+                }
+                "ZREM" => {
+                    let set = self.zsets.entry(arg(1)).or_default();
+                    let before = set.len();
+                    set.retain(|(_, member)| !args[2..].contains(member));
+                    Reply::Int((before - set.len()) as i64)
+                }
+                "ZREMRANGEBYSCORE" => {
+                    let max: f64 = arg(3).parse().unwrap();
+                    let set = self.zsets.entry(arg(1)).or_default();
+                    let before = set.len();
+                    set.retain(|(score, _)| *score > max);
+                    Reply::Int((before - set.len()) as i64)
+                }
+                "ZCARD" => Reply::Int(self.zsets.get(&arg(1)).map_or(0, Vec::len) as i64),
+                "ZRANGE" => {
+                    let mut set = self.zsets.get(&arg(1)).cloned().unwrap_or_default();
+                    set.sort_by(|a, b| a.0.total_cmp(&b.0));
+                    Reply::Array(set.into_iter().map(|(_, member)| Reply::Bulk(Some(member.into_bytes()))).collect())
+                }
+                command => Reply::Error(format!("unknown command '{}'", command)),
+            }
+        }
+    }
+
+    // Read one command, sent as an array of bulk strings
+    fn read_command(reader: &mut impl BufRead) -> Option<Vec<String>> {
+        let mut line = String::new();
+        reader.read_line(&mut line).ok().filter(|read| *read > 0)?;
+        let count: usize = line.trim_end().strip_prefix('*')?.parse().ok()?;
+        (0..count)
+            .map(|_| {
+                let mut header = String::new();
+                reader.read_line(&mut header).ok()?;
+                let len: usize = header.trim_end().strip_prefix('$')?.parse().ok()?;
+                let mut bytes = vec![0; len + 2];
+                reader.read_exact(&mut bytes).ok()?;
+                bytes.truncate(len);
+                String::from_utf8(bytes).ok()
+            })
+            .collect()
+    }
+
+    fn serve(stream: TcpStream, state: Arc<Mutex<MockRedis>>) {
+        let mut writer = stream.try_clone().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut queued: Option<Vec<Vec<String>>> = None;
+        while let Some(args) = read_command(&mut reader) {
+            let reply = match (args[0].to_ascii_uppercase().as_str(), queued.as_mut()) {
+                ("MULTI", _) => {
+                    queued = Some(Vec::new());
+                    Reply::Status("OK")
+                }
+                ("EXEC", Some(_)) if state.lock().unwrap().fail_transactions => {
+                    queued = None;
+                    Reply::Error("EXECABORT Transaction discarded".to_string())
+                }
+                ("EXEC", Some(_)) => {
+                    let mut state = state.lock().unwrap();
+                    Reply::Array(queued.take().unwrap().iter().map(|args| state.execute(args)).collect())
+                }
+                (_, Some(commands)) => {
+                    commands.push(args);
+                    Reply::Status("QUEUED")
+                }
+                (_, None) => state.lock().unwrap().execute(&args),
+            };
+            let mut out = Vec::new();
+            reply.write_to(&mut out);
+            if writer.write_all(&out).is_err() {
+                return;
+            }
+        }
+    }
+
+    fn mock_cache() -> (RedisCache<String>, Arc<Mutex<MockRedis>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("redis://{}", listener.local_addr().unwrap());
+        let state = Arc::new(Mutex::new(MockRedis::default()));
+        std::thread::spawn({
+            let state = state.clone();
+            move || {
+                for stream in listener.incoming().flatten() {
+                    let state = state.clone();
+                    std::thread::spawn(move || serve(stream, state));
+                }
+            }
+        });
+        let cache = RedisCache::new(CacheConfig {
+            redis_url: Some(Secret::new(&url)),
+            ..Default::default()
+        })
+        .unwrap();
+        (cache, state)
+    }
+
+    #[test]
+    fn test_insert_get_remove_against_mock_server() {
+        let (cache, state) = mock_cache();
+        cache.insert("/pokemon/25".to_string(), "pikachu".to_string()).unwrap();
+        cache.insert("/pokemon/1".to_string(), "bulbasaur".to_string()).unwrap();
+
+        assert_eq!(cache.get("/pokemon/25").as_deref().map(String::as_str), Some("pikachu"));
+        assert_eq!(cache.get("/pokemon/4"), None);
+        assert_eq!(cache.size(), 2);
+        let mut keys = cache.keys();
+        keys.sort();
+        assert_eq!(keys, ["/pokemon/1", "/pokemon/25"]);
+        assert!(state.lock().unwrap().hashes.contains_key("pokemon-api-proxy:/pokemon/25"));
+
+        assert_eq!(cache.remove("/pokemon/25").as_deref().map(String::as_str), Some("pikachu"));
+        assert_eq!(cache.get("/pokemon/25"), None);
+        assert_eq!(cache.size(), 1);
+
+        cache.clear();
+        assert_eq!(cache.size(), 0);
+        assert!(state.lock().unwrap().hashes.is_empty());
+        assert_eq!(cache.stats().unwrap().hits, 0);
+    }
+
+    #[test]
+    fn test_entry_age_uses_the_written_ttl() {
+        let (cache, _state) = mock_cache();
+        cache
+            .insert_with_ttl("/pokemon/25".to_string(), "pikachu".to_string(), Duration::from_secs(30))
+            .unwrap();
+
+        // Not the config's hour-long default
+        let age = cache.entry_age("/pokemon/25").unwrap();
+        assert!(age.ttl_remaining <= Duration::from_secs(30));
+        assert!(age.age < Duration::from_secs(1));
+        assert_eq!(cache.entry_age("/pokemon/4"), None);
+    }
+
+    #[test]
+    fn test_expired_entries_leave_reads_and_size() {
+        let (cache, _state) = mock_cache();
+        cache
+            .insert_with_ttl("/pokemon/25".to_string(), "pikachu".to_string(), Duration::from_millis(10))
+            .unwrap();
+        cache.insert("/pokemon/1".to_string(), "bulbasaur".to_string()).unwrap();
+
+        // The server keeps real time, so wait out the short TTL
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(cache.get("/pokemon/25"), None);
+        assert_eq!(cache.entry_age("/pokemon/25"), None);
+        assert_eq!(cache.size(), 1);
+        assert_eq!(cache.keys(), ["/pokemon/1"]);
+    }
+
+    #[tokio::test]
+    async fn test_failed_writes_are_not_counted() {
+        let (cache, state) = mock_cache();
+        cache.insert("/pokemon/1".to_string(), "bulbasaur".to_string()).unwrap();
+        assert_eq!(cache.stats().unwrap().inserts, 1);
+
+        state.lock().unwrap().fail_transactions = true;
+        assert!(cache.insert("/pokemon/25".to_string(), "pikachu".to_string()).is_err());
+        let async_insert =
+            async_cache::AsyncCacheTrait::insert(&cache, "/pokemon/4".to_string(), "charmander".to_string()).await;
+        assert!(async_insert.is_err());
+        assert_eq!(cache.stats().unwrap().inserts, 1);
+    }
+
+    #[tokio::test]
+    async fn test_async_calls_against_mock_server() {
+        let (cache, _state) = mock_cache();
+        async_cache::AsyncCacheTrait::insert(&cache, "/pokemon/25".to_string(), "pikachu".to_string())
+            .await
+            .unwrap();
+        assert_eq!(
+            async_cache::AsyncCacheTrait::get(&cache, "/pokemon/25").await.as_deref().map(String::as_str),
+            Some("pikachu")
+        );
+        assert_eq!(async_cache::AsyncCacheTrait::size(&cache).await, 1);
+        assert_eq!(async_cache::AsyncCacheTrait::keys(&cache).await, ["/pokemon/25"]);
+        assert_eq!(
+            async_cache::AsyncCacheTrait::peek(&cache, "/pokemon/25").await.as_deref().map(String::as_str),
+            Some("pikachu")
+        );
+        assert!(async_cache::AsyncCacheTrait::entry_age(&cache, "/pokemon/25").await.is_some());
+        assert_eq!(async_cache::AsyncCacheTrait::entry_age(&cache, "/pokemon/4").await, None);
+        assert!(async_cache::AsyncCacheTrait::remove(&cache, "/pokemon/25").await.is_some());
+        async_cache::AsyncCacheTrait::clear(&cache).await;
+        assert_eq!(cache.size(), 0);
+    }
+}
@@ -0,0 +1,84 @@
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use std::hash::{BuildHasher, Hasher};
+use tracing::Instrument;
+
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+// Longest client-supplied id that is kept; longer ones are replaced
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+// A client's `X-Request-Id`, if it is short printable ASCII worth echoing
+fn client_request_id(request: &Request<Body>) -> Option<HeaderValue> {
+    let value = request.headers().get(&REQUEST_ID_HEADER)?;
+    let id = value.to_str().ok()?;
+    (!id.is_empty() && id.len() <= MAX_REQUEST_ID_LENGTH && id.bytes().all(|b| b.is_ascii_graphic()))
+        .then(|| value.clone())
+}
+
+// 32 hex digits from two freshly seeded hashers, without depending on `rand`
+fn generate_request_id() -> HeaderValue {
+    let half = || std::collections::hash_map::RandomState::new().build_hasher().finish();
+    let id = format!("{:016x}{:016x}", half(), half());
+    HeaderValue::from_str(&id).expect("hex digits are a valid header value")
+}
+
+// Middleware giving every request an id: the client's `X-Request-Id` when it
+// sends a usable one, else a generated one. Everything logged while handling
+// the request carries it and the request path through a span, and the id is
+// echoed in the response.
+pub async fn request_id(request: Request<Body>, next: Next) -> Response {
+    let id = client_request_id(&request).unwrap_or_else(generate_request_id);
+
+    let span = tracing::info_span!(
+        "request",
+        request_id = %String::from_utf8_lossy(id.as_bytes()),
+        method = %request.method(),
+        path = %request.uri().path(),
+    );
+    let mut response = next.run(request).instrument(span).await;
+    response.headers_mut().insert(REQUEST_ID_HEADER, id);
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{middleware, routing::get, Router};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_request_id_generated_or_preserved() {
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(middleware::from_fn(request_id));
+        let request = |id: Option<&str>| {
+            let mut request = Request::get("/");
+            if let Some(id) = id {
+                request = request.header(&REQUEST_ID_HEADER, id);
+            }
+            request.body(Body::empty()).unwrap()
+        };
+
+        let response = app.clone().oneshot(request(Some("abc-123"))).await.unwrap();
+        assert_eq!(response.headers()[&REQUEST_ID_HEADER], "abc-123");
+
+        // Missing, or not worth echoing: a fresh id per request
+        let long = "x".repeat(MAX_REQUEST_ID_LENGTH + 1);
+        let mut generated = Vec::new();
+        for id in [None, Some("has space"), Some(long.as_str())] {
+            let response = app.clone().oneshot(request(id)).await.unwrap();
+            let id = response.headers()[&REQUEST_ID_HEADER].to_str().unwrap().to_string();
+            assert_eq!(id.len(), 32);
+            assert!(id.bytes().all(|b| b.is_ascii_hexdigit()));
+            generated.push(id);
+        }
+        generated.dedup();
+        assert_eq!(generated.len(), 3);
+    }
+}
@@ -0,0 +1,85 @@
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+pub const SERVER_TIMING_HEADER: HeaderName = HeaderName::from_static("server-timing");
+
+// Time a request spent in each phase; several lookups in one request add up
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Timings {
+    pub cache: Duration,
+    pub upstream: Duration,
+}
+
+tokio::task_local! {
+    static TIMINGS: Cell<Timings>;
+}
+
+// Add to the current request's cache time (no-op outside the middleware)
+pub fn record_cache(elapsed: Duration) {
+    let _ = TIMINGS.try_with(|timings| {
+        let mut current = timings.get();
+        current.cache += elapsed;
+        timings.set(current);
+    });
+}
+
+// Add to the current request's upstream time (no-op outside the middleware)
+pub fn record_upstream(elapsed: Duration) {
+    let _ = TIMINGS.try_with(|timings| {
+        let mut current = timings.get();
+        current.upstream += elapsed;
+        timings.set(current);
+    });
+}
+
+// Render as `cache;dur=0.012, upstream;dur=48.210, total;dur=48.900` (milliseconds)
+pub fn header_value(timings: Timings, total: Duration) -> String {
+    let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+    format!(
+        "cache;dur={:.3}, upstream;dur={:.3}, total;dur={:.3}",
+        ms(timings.cache),
+        ms(timings.upstream),
+        ms(total)
+    )
+}
+
+// Middleware collecting phase timings for the request and reporting them in a
+// `Server-Timing` header
+pub async fn server_timing(request: Request<Body>, next: Next) -> Response {
+    let started = Instant::now();
+    let (mut response, timings) = TIMINGS
+        .scope(Cell::new(Timings::default()), async {
+            let response = next.run(request).await;
+            (response, TIMINGS.with(Cell::get))
+        })
+        .await;
+
+    if let Ok(value) = HeaderValue::from_str(&header_value(timings, started.elapsed())) {
+        response.headers_mut().insert(SERVER_TIMING_HEADER, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_value_format() {
+        let timings = Timings {
+            cache: Duration::from_micros(12),
+            upstream: Duration::from_micros(48_210),
+        };
+        assert_eq!(
+            header_value(timings, Duration::from_micros(48_900)),
+            "cache;dur=0.012, upstream;dur=48.210, total;dur=48.900"
+        );
+    }
+}
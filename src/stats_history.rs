@@ -0,0 +1,144 @@
+use crate::cache::CacheStats;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// How often the server snapshots cache stats, and how many snapshots it keeps
+// (one hour at the default interval)
+pub const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(1);
+const MAX_SNAPSHOTS: usize = 3600;
+
+// Rolling history of cache stats snapshots, used to turn the cumulative
+// counters into per-window deltas
+#[derive(Debug, Default)]
+pub struct StatsHistory {
+    snapshots: Mutex<VecDeque<(Instant, CacheStats)>>,
+}
+
+// Change in each counter over a window, plus per-second rates for the busy ones
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct StatsRate {
+    pub window_secs: u64,
+    pub hits: u64,
+    pub misses: u64,
+    pub inserts: u64,
+    pub removes: u64,
+    pub evictions: u64,
+    pub cleanups: u64,
+    pub hits_per_sec: f64,
+    pub misses_per_sec: f64,
+}
+
+impl StatsHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, stats: CacheStats) {
+        self.record_at(Instant::now(), stats);
+    }
+
+    fn record_at(&self, at: Instant, stats: CacheStats) {
+        if let Ok(mut snapshots) = self.snapshots.lock() {
+            snapshots.push_back((at, stats));
+            while snapshots.len() > MAX_SNAPSHOTS {
+                snapshots.pop_front();
+            }
+        }
+    }
+
+    // Delta between `current` and the newest snapshot at least `window` old.
+    // Fails when the window is empty or reaches back past the recorded history.
+    pub fn rate(&self, current: &CacheStats, window: Duration) -> Result<StatsRate, String> {
+        self.rate_at(Instant::now(), current, window)
+    }
+
+    fn rate_at(&self, now: Instant, current: &CacheStats, window: Duration) -> Result<StatsRate, String> {
+        if window.is_zero() {
+            return Err("window_secs must be at least 1".to_string());
+        }
+
+        let snapshots = self
+            .snapshots
+            .lock()
+            .map_err(|e| format!("Stats history unavailable: {}", e))?;
+        let (taken_at, baseline) = snapshots
+            .iter()
+            .rev()
+            .find(|(at, _)| now.saturating_duration_since(*at) >= window)
+            .ok_or_else(|| {
+                let available = snapshots
+                    .front()
+                    .map_or(0, |(at, _)| now.saturating_duration_since(*at).as_secs());
+                format!(
+                    "window_secs {} exceeds the {} seconds of stats history available",
+                    window.as_secs(),
+                    available
+                )
+            })?;
+
+        let elapsed = now.saturating_duration_since(*taken_at).as_secs_f64();
+        let hits = current.hits.saturating_sub(baseline.hits);
+        let misses = current.misses.saturating_sub(baseline.misses);
+
+        Ok(StatsRate {
+            window_secs: window.as_secs(),
+            hits,
+            misses,
+            inserts: current.inserts.saturating_sub(baseline.inserts),
+            removes: current.removes.saturating_sub(baseline.removes),
+            evictions: current.evictions.saturating_sub(baseline.evictions),
+            cleanups: current.cleanups.saturating_sub(baseline.cleanups),
+            hits_per_sec: hits as f64 / elapsed,
+            misses_per_sec: misses as f64 / elapsed,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::{CacheTrait, InmemoryCache};
+
+    #[test]
+    fn test_rate_reflects_delta_over_window() {
+        let cache: InmemoryCache<String> = InmemoryCache::with_defaults();
+        let history = StatsHistory::new();
+        let start = Instant::now();
+
+        // Activity before the window must not count
+        cache.insert("/pokemon/1".to_string(), "{}".to_string()).unwrap();
+        cache.get("/pokemon/1");
+        history.record_at(start, cache.stats().unwrap());
+
+        cache.insert("/pokemon/25".to_string(), "{}".to_string()).unwrap();
+        for _ in 0..6 {
+            cache.get("/pokemon/25");
+        }
+        cache.get("/pokemon/999");
+        cache.get("/pokemon/1000");
+
+        let rate = history
+            .rate_at(start + Duration::from_secs(2), &cache.stats().unwrap(), Duration::from_secs(2))
+            .unwrap();
+        assert_eq!(rate.window_secs, 2);
+        assert_eq!(rate.inserts, 1);
+        assert_eq!(rate.hits, 6);
+        assert_eq!(rate.misses, 2);
+        assert_eq!(rate.hits_per_sec, 3.0);
+        assert_eq!(rate.misses_per_sec, 1.0);
+    }
+
+    #[test]
+    fn test_rate_rejects_window_beyond_history() {
+        let history = StatsHistory::new();
+        let start = Instant::now();
+        history.record_at(start, CacheStats::default());
+
+        let now = start + Duration::from_secs(30);
+        assert!(history.rate_at(now, &CacheStats::default(), Duration::from_secs(60)).is_err());
+        assert!(history.rate_at(now, &CacheStats::default(), Duration::ZERO).is_err());
+        assert!(history.rate_at(now, &CacheStats::default(), Duration::from_secs(30)).is_ok());
+    }
+}
@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+// A successful upstream response as stored in the cache
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UpstreamResponse {
+    pub body: String,
+    // The upstream's Content-Type header, if it sent one
+    pub content_type: Option<String>,
+}
+
+// Backoff never grows past this, however many retries are configured
+const MAX_BACKOFF: Duration = Duration::from_secs(10);
+
+// Delay before retry `attempt` (1-based): `base` doubled per attempt, with the
+// upper half randomized by `jitter` (0.0..1.0) so retries from many requests
+// don't land on the upstream in lockstep
+pub fn backoff_delay(base: Duration, attempt: u32, jitter: f64) -> Duration {
+    let exponential = base
+        .checked_mul(1 << attempt.saturating_sub(1).min(16))
+        .unwrap_or(MAX_BACKOFF)
+        .min(MAX_BACKOFF);
+    exponential / 2 + exponential.mul_f64(jitter.clamp(0.0, 1.0) / 2.0)
+}
+
+// A random value in 0.0..1.0 for jitter. Each `RandomState` is freshly
+// seeded, which is random enough here without depending on `rand`.
+pub fn jitter() -> f64 {
+    let bits = std::collections::hash_map::RandomState::new().build_hasher().finish();
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_delay_doubles_within_jitter() {
+        let base = Duration::from_millis(100);
+        assert_eq!(backoff_delay(base, 1, 0.0), Duration::from_millis(50));
+        assert_eq!(backoff_delay(base, 1, 1.0), Duration::from_millis(100));
+        assert_eq!(backoff_delay(base, 3, 0.0), Duration::from_millis(200));
+        assert_eq!(backoff_delay(base, 3, 1.0), Duration::from_millis(400));
+        assert_eq!(backoff_delay(base, 40, 1.0), MAX_BACKOFF);
+
+        let jitter = jitter();
+        assert!((0.0..1.0).contains(&jitter));
+    }
+}